@@ -44,13 +44,21 @@ bool emulator_set_state(RetroEmulator* h, const char* state_data, size_t state_s
     return h->emulator->unserialize(state_data, state_size);
 }
 
+size_t emulator_get_state_size(RetroEmulator* h) {
+    return h->emulator->serializeSize();
+}
+
+bool emulator_get_state(RetroEmulator* h, uint8_t* state_data, size_t state_size) {
+    return h->emulator->serialize(state_data, state_size);
+}
+
 int emulator_get_screen_width(RetroEmulator* h) {
     long width = h->emulator->getImageWidth();
     return static_cast<int>(width);
 }
 
 int emulator_get_screen_height(RetroEmulator* h) {
-    long height = h->emulator->getImageWidth();
+    long height = h->emulator->getImageHeight();
     return static_cast<int>(height);
 }
 
@@ -85,7 +93,7 @@ bool load_core_info(const char* json) {
 }
 
 void emulator_configure_data(RetroEmulator* h, RetroGameData* data) {
-    h->emulator->configureData(data->data);
+    h->emulator->configureData(gamedata_game_data(data));
 }
 
 std::string emulator_get_core(RetroEmulator* handle) {
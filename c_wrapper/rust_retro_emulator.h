@@ -27,6 +27,8 @@ bool emulator_load_rom(RetroEmulator* h, const char* rom_path);
 void emulator_run(RetroEmulator* handle);
 
 bool emulator_set_state(RetroEmulator* h, const char* state_data, size_t state_size);
+size_t emulator_get_state_size(RetroEmulator* h);
+bool emulator_get_state(RetroEmulator* h, uint8_t* state_data, size_t state_size);
 
 int emulator_get_screen_width(RetroEmulator* h);
 int emulator_get_screen_height(RetroEmulator* h);
@@ -1,7 +1,88 @@
 #include "rust_retro_gamedata.h"
 #include "data.h"
+#include "emulator.h"
+#include "script.h"
+#include <algorithm>
 #include <stdlib.h>
+#include <string.h>
+#include <memory>
+#include <sstream>
+#include <stdexcept>
 #include <string>
+#include <vector>
+
+// Reward and done accumulators of the scenario, mirrored after every update so a snapshot
+// can restore them. Retro::Scenario has no setters for its own.
+struct RetroProgress {
+    std::vector<float> rewards;
+    std::vector<float> total_rewards;
+    bool done = false;
+    uint64_t frame = 0;
+};
+struct RetroGameData {
+    Retro::GameData* data;
+    Retro::Scenario* scenario;
+    RetroProgress progress;
+    // Why a script failed to load or run, empty if all is well
+    std::string script_error;
+};
+
+// Function name and scope of "scope:name" like in scenario files, the scope is optional
+static std::pair<std::string, std::string> split_function(const std::string& function) {
+    size_t colon = function.find(':');
+    if (colon == std::string::npos) {
+        return { function, {} };
+    }
+    return { function.substr(colon + 1), function.substr(0, colon) };
+}
+
+static std::string join_function(const std::pair<std::string, std::string>& function) {
+    return function.second.empty() ? function.first : function.second + ":" + function.first;
+}
+
+static bool has_function(const std::pair<std::string, std::string>& function) {
+    auto context = Retro::ScriptContext::get(function.second);
+    if (!context) {
+        return false;
+    }
+    const auto functions = context->listFunctions();
+    return std::find(functions.begin(), functions.end(), function.first) != functions.end();
+}
+
+// Records why the scripts of the scenario can not run, false if they can. Scenario::load
+// skips scripts that fail to load, so the expected ones are checked against the loaded.
+static bool scripts_failed(
+    RetroGameData* h,
+    const Retro::Scenario& scenario,
+    const char* const* scripts,
+    size_t num_scripts
+) {
+    const auto loaded = scenario.scripts();
+    for (size_t i = 0; i < num_scripts; i++) {
+        const std::string script = scripts[i];
+        const auto found = std::find_if(loaded.begin(), loaded.end(), [&](const auto& entry) {
+            return entry.first == script;
+        });
+        if (found == loaded.end()) {
+            h->script_error = "Failed to load " + script;
+            return true;
+        }
+    }
+    for (const auto& function : { scenario.rewardFunction(), scenario.doneFunction() }) {
+        if (!function.first.empty() && !has_function(function)) {
+            h->script_error = "Lua function " + join_function(function) + " is not defined";
+            return true;
+        }
+    }
+    return false;
+}
+
+static void clear_progress(RetroGameData* h) {
+    h->progress.rewards.assign(Retro::MAX_PLAYERS, 0.0f);
+    h->progress.total_rewards.assign(Retro::MAX_PLAYERS, 0.0f);
+    h->progress.done = false;
+    h->progress.frame = 0;
+}
 
 /////////////////////////////////////
 ///////////Create/Free///////////////
@@ -11,9 +92,18 @@ RetroGameData* gamedata_new() {
     RetroGameData* h = new RetroGameData;
     h->data = new Retro::GameData();
     h->scenario = new Retro::Scenario(*h->data);
+    clear_progress(h);
     return h;
 }
 
+void gamedata_free(RetroGameData* h) {
+    if (!h) return;
+    // The scenario references the game data, so it goes first
+    delete h->scenario;
+    delete h->data;
+    delete h;
+}
+
 RetroActionSet* gamedata_valid_actions(RetroGameData* h, size_t* num_entries) {
     const auto& actions = h->scenario->validActions();
 
@@ -43,38 +133,282 @@ void gamedata_free_valid_actions(RetroActionSet* actions, size_t num_entries) {
     free(actions);
 }
 
+RetroVariable* gamedata_lookup_all(RetroGameData* h, size_t* num_entries) {
+    const auto variables = static_cast<const Retro::GameData*>(h->data)->lookupAll();
+
+    *num_entries = variables.size();
+    RetroVariable* result = (RetroVariable*)malloc(sizeof(RetroVariable) * variables.size());
+
+    size_t i = 0;
+    for (const auto& [name, value] : variables) {
+        result[i].name = strdup(name.c_str());
+        result[i].value = value;
+        i++;
+    }
+
+    return result;
+}
+
+void gamedata_free_variables(RetroVariable* variables, size_t num_entries) {
+    for (size_t i = 0; i < num_entries; i++) {
+        free(variables[i].name);
+    }
+    free(variables);
+}
+
+char** gamedata_scripts(RetroGameData* h, size_t* num_scripts) {
+    const auto scripts = h->scenario->scripts();
+
+    *num_scripts = scripts.size();
+    char** result = (char**)malloc(sizeof(char*) * scripts.size());
+    for (size_t i = 0; i < scripts.size(); i++) {
+        result[i] = strdup(scripts[i].first.c_str());
+    }
+    return result;
+}
+
+void gamedata_free_strings(char** strings, size_t num_strings) {
+    for (size_t i = 0; i < num_strings; i++) {
+        free(strings[i]);
+    }
+    free(strings);
+}
+
+// NULL if the reward comes from the variables of the scenario
+char* gamedata_reward_function(RetroGameData* h) {
+    const auto function = h->scenario->rewardFunction();
+    return function.first.empty() ? nullptr : strdup(join_function(function).c_str());
+}
+
+char* gamedata_done_function(RetroGameData* h) {
+    const auto function = h->scenario->doneFunction();
+    return function.first.empty() ? nullptr : strdup(join_function(function).c_str());
+}
+
+void gamedata_free_string(char* string) {
+    free(string);
+}
+
+// The block data points into the emulator memory and is only valid until the next frame
+RetroMemoryBlock* gamedata_memory_blocks(RetroGameData* h, size_t* num_blocks) {
+    const auto& blocks = h->data->addressSpace().blocks();
+
+    *num_blocks = blocks.size();
+    RetroMemoryBlock* result = (RetroMemoryBlock*)malloc(sizeof(RetroMemoryBlock) * blocks.size());
+
+    size_t i = 0;
+    for (const auto& [offset, block] : blocks) {
+        result[i].offset = offset;
+        result[i].size = block.size();
+        result[i].data = static_cast<const uint8_t*>(block.offset(0));
+        i++;
+    }
+
+    return result;
+}
+
+void gamedata_free_memory_blocks(RetroMemoryBlock* blocks) {
+    free(blocks);
+}
+
 
 
 /////////////////////////////////////
 /////////////Methods/////////////////
 /////////////////////////////////////
 
-bool gamedata_load(RetroGameData* h, const char* data_path, const char* scenario_path) {
-    bool success = true;
-    success = success && h->data->load(data_path);
-    success = success && h->scenario->load(scenario_path);
+// Variables of data.json and the ones created by scripts, false if the name is unknown
+bool gamedata_lookup_value(RetroGameData* h, const char* name, int64_t* value) {
+    try {
+        *value = h->data->lookupValue(name);
+        return true;
+    } catch (const std::exception&) {
+        return false;
+    }
+}
+
+bool gamedata_lookup_delta(RetroGameData* h, const char* name, int64_t* delta) {
+    int64_t value;
+    if (!gamedata_lookup_value(h, name, &value)) {
+        return false;
+    }
+    *delta = static_cast<const Retro::GameData*>(h->data)->lookupDelta(name);
+    return true;
+}
+
+bool gamedata_set_value(RetroGameData* h, const char* name, int64_t value) {
+    int64_t old_value;
+    // GameData would silently create a new custom variable for an unknown name
+    if (!gamedata_lookup_value(h, name, &old_value)) {
+        return false;
+    }
+    try {
+        h->data->setValue(name, value);
+    } catch (const std::exception&) {
+        return false;
+    }
+    // Resync the memory copy, so rewards measuring deltas do not count the write
+    h->data->updateRam();
+    return true;
+}
+
+bool gamedata_load_data(RetroGameData* h, const char* data_path) {
+    return h->data->load(data_path);
+}
+
+bool gamedata_load_scenario(
+    RetroGameData* h,
+    const char* scenario_path,
+    const char* const* scripts,
+    size_t num_scripts
+) {
+    h->script_error.clear();
+    return h->scenario->load(scenario_path) && !scripts_failed(h, *h->scenario, scripts, num_scripts);
+}
+
+bool gamedata_load_scenario_json(
+    RetroGameData* h,
+    const char* scenario_json,
+    const char* base_dir,
+    const char* const* scripts,
+    size_t num_scripts
+) {
+    // Loading into the existing scenario would keep its done nodes, scripts and actions
+    auto scenario = std::make_unique<Retro::Scenario>(*h->data);
+    std::istringstream stream(scenario_json);
+    h->script_error.clear();
+    try {
+        if (!scenario->load(&stream, std::string(base_dir) + "/scenario.json") || scripts_failed(h, *scenario, scripts, num_scripts)) {
+            // Points the script contexts back to the scenario that is kept
+            h->scenario->reloadScripts();
+            return false;
+        }
+    } catch (const std::exception&) {
+        h->scenario->reloadScripts();
+        return false;
+    }
+
+    delete h->scenario;
+    h->scenario = scenario.release();
+    clear_progress(h);
+    return true;
+}
 
+bool gamedata_load_script(RetroGameData* h, const char* script_path, const char* scope) {
+    h->script_error.clear();
+    if (!h->scenario->loadScript(script_path, scope)) {
+        h->script_error = std::string("Failed to load ") + script_path;
+        return false;
+    }
+    return true;
+}
+
+bool gamedata_set_reward_function(RetroGameData* h, const char* function) {
+    const auto parts = split_function(function);
+    if (!has_function(parts)) {
+        h->script_error = std::string("Lua function ") + function + " is not defined";
+        return false;
+    }
+    h->scenario->setRewardFunction(parts.first, parts.second);
     return true;
 }
 
+bool gamedata_set_done_function(RetroGameData* h, const char* function) {
+    const auto parts = split_function(function);
+    if (!has_function(parts)) {
+        h->script_error = std::string("Lua function ") + function + " is not defined";
+        return false;
+    }
+    h->scenario->setDoneFunction(parts.first, parts.second);
+    return true;
+}
+
+// Valid until the next call into the game data, NULL without an error
+const char* gamedata_script_error(RetroGameData* h) {
+    return h->script_error.empty() ? nullptr : h->script_error.c_str();
+}
+
 void gamedata_reset(RetroGameData* h) {
+    h->script_error.clear();
     h->scenario->restart();
     h->scenario->reloadScripts();
+    clear_progress(h);
+}
+
+Retro::GameData* gamedata_game_data(RetroGameData* h) {
+    return h->data;
 }
 
 void gamedata_update_ram(RetroGameData* h) {
     h->data->updateRam();
-    h->scenario->update();
+    // Lua errors of the reward or done function must not unwind into Rust
+    try {
+        h->scenario->update();
+    } catch (const std::exception& e) {
+        // Reward and done of the failed frame are undefined, report neither
+        h->script_error = e.what();
+        h->progress.rewards.assign(Retro::MAX_PLAYERS, 0.0f);
+        h->progress.done = false;
+        ++h->progress.frame;
+        return;
+    }
+
+    // Accumulated like Scenario::update does, but on top of a restored snapshot
+    for (unsigned i = 0; i < Retro::MAX_PLAYERS; ++i) {
+        h->progress.rewards[i] = h->scenario->currentReward(i);
+        h->progress.total_rewards[i] += h->progress.rewards[i];
+    }
+    h->progress.done = h->scenario->isDone();
+    ++h->progress.frame;
 }
 
 float gamedata_current_reward(RetroGameData* h) {
-    return h->scenario->currentReward(0);
+    return h->progress.rewards[0];
 }
 
 float gamedata_total_reward(RetroGameData* h) {
-    return h->scenario->totalReward(0);
+    return h->progress.total_rewards[0];
 }
 
 bool gamedata_is_done(RetroGameData* h) {
-    return h->scenario->isDone();
-}
\ No newline at end of file
+    return h->progress.done;
+}
+
+uint64_t gamedata_frame(RetroGameData* h) {
+    return h->progress.frame;
+}
+
+// Like Scenario::timestep, counted from the mirrored frame
+uint64_t gamedata_timestep(RetroGameData* h) {
+    return h->progress.frame / 4;
+}
+
+void gamedata_get_crop(RetroGameData* h, size_t* x, size_t* y, size_t* width, size_t* height, unsigned player) {
+    h->scenario->getCrop(x, y, width, height, player);
+}
+
+void gamedata_set_crop(RetroGameData* h, size_t x, size_t y, size_t width, size_t height, unsigned player) {
+    h->scenario->setCrop(x, y, width, height, player);
+}
+
+size_t gamedata_max_players() {
+    return Retro::MAX_PLAYERS;
+}
+
+// The arrays hold gamedata_max_players() entries
+void gamedata_get_progress(RetroGameData* h, float* rewards, float* total_rewards, bool* done, uint64_t* frame) {
+    std::copy(h->progress.rewards.begin(), h->progress.rewards.end(), rewards);
+    std::copy(h->progress.total_rewards.begin(), h->progress.total_rewards.end(), total_rewards);
+    *done = h->progress.done;
+    *frame = h->progress.frame;
+}
+
+// Call after restoring the emulator state the progress belongs to
+void gamedata_set_progress(RetroGameData* h, const float* rewards, const float* total_rewards, bool done, uint64_t frame) {
+    h->progress.rewards.assign(rewards, rewards + Retro::MAX_PLAYERS);
+    h->progress.total_rewards.assign(total_rewards, total_rewards + Retro::MAX_PLAYERS);
+    h->progress.done = done;
+    h->progress.frame = frame;
+    // Deltas of the next update are measured against the restored memory
+    h->data->updateRam();
+}
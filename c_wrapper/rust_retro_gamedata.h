@@ -1,46 +1,94 @@
 #pragma once
 
-#include <cstdint>
-#include <cstddef>
+#include <stdint.h>
+#include <stddef.h>
+#include <stdbool.h>
 
 #ifdef __cplusplus
+namespace Retro {
+    class GameData;
+}
+
 extern "C" {
 #endif
 
 // Opaque handle
-namespace Retro {
-    class GameData;
-    class Scenario;
-}
-struct RetroGameData {
-    Retro::GameData* data;
-    Retro::Scenario* scenario;
-};
+typedef struct RetroGameData RetroGameData;
 typedef struct {
     int key;
     int* values;
     size_t num_values;
 } RetroActionSet;
+typedef struct {
+    char* name;
+    int64_t value;
+} RetroVariable;
+typedef struct {
+    size_t offset;
+    size_t size;
+    const uint8_t* data;
+} RetroMemoryBlock;
 
 /////////////////////////////////////
 ///////////Create/Free///////////////
 /////////////////////////////////////
 
 RetroGameData* gamedata_new();
+void gamedata_free(RetroGameData* h);
 RetroActionSet* gamedata_valid_actions(RetroGameData* h, size_t* num_entries);
 void gamedata_free_valid_actions(RetroActionSet* actions, size_t num_entries);
+RetroVariable* gamedata_lookup_all(RetroGameData* h, size_t* num_entries);
+void gamedata_free_variables(RetroVariable* variables, size_t num_entries);
+RetroMemoryBlock* gamedata_memory_blocks(RetroGameData* h, size_t* num_blocks);
+void gamedata_free_memory_blocks(RetroMemoryBlock* blocks);
+char** gamedata_scripts(RetroGameData* h, size_t* num_scripts);
+void gamedata_free_strings(char** strings, size_t num_strings);
+char* gamedata_reward_function(RetroGameData* h);
+char* gamedata_done_function(RetroGameData* h);
+void gamedata_free_string(char* string);
 
 /////////////////////////////////////
 /////////////Methods/////////////////
 /////////////////////////////////////
 
-bool gamedata_load(RetroGameData* h, const char* data_path, const char* scenario_path);
+bool gamedata_lookup_value(RetroGameData* h, const char* name, int64_t* value);
+bool gamedata_lookup_delta(RetroGameData* h, const char* name, int64_t* delta);
+bool gamedata_set_value(RetroGameData* h, const char* name, int64_t value);
+bool gamedata_load_data(RetroGameData* h, const char* data_path);
+// The scenario fails to load if one of the expected scripts of its "scripts" list does not
+bool gamedata_load_scenario(
+    RetroGameData* h,
+    const char* scenario_path,
+    const char* const* scripts,
+    size_t num_scripts
+);
+bool gamedata_load_scenario_json(
+    RetroGameData* h,
+    const char* scenario_json,
+    const char* base_dir,
+    const char* const* scripts,
+    size_t num_scripts
+);
+bool gamedata_load_script(RetroGameData* h, const char* script_path, const char* scope);
+bool gamedata_set_reward_function(RetroGameData* h, const char* function);
+bool gamedata_set_done_function(RetroGameData* h, const char* function);
+const char* gamedata_script_error(RetroGameData* h);
 void gamedata_reset(RetroGameData* h);
 void gamedata_update_ram(RetroGameData* h);
 float gamedata_current_reward(RetroGameData* h);
 float gamedata_total_reward(RetroGameData* h);
 bool gamedata_is_done(RetroGameData* h);
+uint64_t gamedata_frame(RetroGameData* h);
+uint64_t gamedata_timestep(RetroGameData* h);
+void gamedata_get_crop(RetroGameData* h, size_t* x, size_t* y, size_t* width, size_t* height, unsigned player);
+void gamedata_set_crop(RetroGameData* h, size_t x, size_t y, size_t width, size_t height, unsigned player);
+size_t gamedata_max_players();
+void gamedata_get_progress(RetroGameData* h, float* rewards, float* total_rewards, bool* done, uint64_t* frame);
+void gamedata_set_progress(RetroGameData* h, const float* rewards, const float* total_rewards, bool done, uint64_t frame);
 
 #ifdef __cplusplus
 }
+
+// Game data behind the handle, to configure an emulator with it
+Retro::GameData* gamedata_game_data(RetroGameData* h);
 #endif
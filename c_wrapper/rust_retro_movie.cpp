@@ -14,12 +14,18 @@ struct RetroMovie {
 ///////////Create/Free///////////////
 /////////////////////////////////////
 
-RetroMovie* movie_new(const char* name) {
+RetroMovie* movie_new(const char* name, unsigned players) {
     RetroMovie* handle = new RetroMovie;
-    handle->movie = std::make_unique<Retro::MovieBK2>(name, true, 1);
+    handle->movie = std::make_unique<Retro::MovieBK2>(name, true, players);
     return handle;
 }
 
+void movie_free(RetroMovie* handle) {
+    if (!handle) return;
+    // The movie is closed by its destructor
+    delete handle;
+}
+
 void movie_close(RetroMovie* handle) {
     handle->movie->close();
 }
@@ -35,8 +41,8 @@ void movie_configure(RetroMovie* movie_handle, RetroEmulator* emulator_handle, c
     static_cast<Retro::MovieBK2*>(movie_handle->movie.get())->setGameName(name);
     static_cast<Retro::MovieBK2*>(movie_handle->movie.get())->loadKeymap(emulator_get_core(emulator_handle));
 }
-void movie_set_key(RetroMovie* handle, int key, bool set) {
-    handle->movie->setKey(key, set, 0);
+void movie_set_key(RetroMovie* handle, int key, bool set, unsigned player) {
+    handle->movie->setKey(key, set, player);
 }
 void movie_set_state(RetroMovie* handle, const uint8_t* data, size_t size) {
     handle->movie->setState(const_cast<uint8_t*>(data), size);
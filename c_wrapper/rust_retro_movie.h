@@ -15,7 +15,8 @@ struct RetroEmulator;
 ///////////Create/Free///////////////
 /////////////////////////////////////
 
-RetroMovie* movie_new(const char* name);
+RetroMovie* movie_new(const char* name, unsigned players);
+void movie_free(RetroMovie* handle);
 void movie_close(RetroMovie* handle);
 
 /////////////////////////////////////
@@ -23,7 +24,7 @@ void movie_close(RetroMovie* handle);
 /////////////////////////////////////
 
 bool movie_step(RetroMovie* handle);
-void movie_set_key(RetroMovie* handle, int key, bool set);
+void movie_set_key(RetroMovie* handle, int key, bool set, unsigned player);
 void movie_configure(RetroMovie* movie_handle, RetroEmulator* emulator_handle, const char* name);
 void movie_set_state(RetroMovie* handle, const uint8_t* data, size_t size);
 
@@ -0,0 +1,106 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::environments::image_retro_env::platform::Platform;
+
+/// Environment variable with extra game integration directories, separated like `PATH`
+pub const DATA_PATH_ENV: &str = "RETRO_RUST_DATA_PATH";
+/// Environment variable with extra libretro core directories, separated like `PATH`
+pub const CORE_PATH_ENV: &str = "RETRO_RUST_CORE_PATH";
+
+/// Search paths for game integrations, save states, ROMs and libretro cores.
+///
+/// Directories are searched in order, so an overlay directory can ship custom
+/// integrations or replace files of bundled ones. Every file of an integration is
+/// looked up separately, e.g. an overlay may only add save states or ROMs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetPaths {
+    /// Directories containing integrations like `SuperMarioBros-Nes`
+    pub data_dirs: Vec<PathBuf>,
+    /// Directories containing `*_libretro` libraries and their `info/*.json`
+    pub core_dirs: Vec<PathBuf>,
+}
+
+impl AssetPaths {
+    /// Only the `games` and `cores` directories shipped with this crate
+    pub fn bundled() -> Self {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        AssetPaths {
+            data_dirs: vec![root.join("games")],
+            core_dirs: vec![root.join("cores")],
+        }
+    }
+
+    /// Directories of `RETRO_RUST_DATA_PATH` and `RETRO_RUST_CORE_PATH`, followed by the bundled ones
+    pub fn from_env() -> Self {
+        let bundled = Self::bundled();
+        AssetPaths {
+            data_dirs: Self::env_dirs(DATA_PATH_ENV).into_iter().chain(bundled.data_dirs).collect(),
+            core_dirs: Self::env_dirs(CORE_PATH_ENV).into_iter().chain(bundled.core_dirs).collect(),
+        }
+    }
+
+    fn env_dirs(name: &str) -> Vec<PathBuf> {
+        env::var_os(name)
+            .map(|value| env::split_paths(&value).filter(|dir| !dir.as_os_str().is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Adds an overlay of game integrations that takes precedence over all directories added before
+    pub fn with_data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.data_dirs.insert(0, dir.into());
+        self
+    }
+
+    /// Adds a core directory that takes precedence over all directories added before
+    pub fn with_core_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.core_dirs.insert(0, dir.into());
+        self
+    }
+
+    /// First integration directory of the game
+    pub fn find_game(&self, game_name: &str, platform: Platform) -> Option<PathBuf> {
+        let game_dir = platform.game_dir(game_name);
+        self.data_dirs
+            .iter()
+            .map(|dir| dir.join(&game_dir))
+            .find(|path| path.is_dir())
+    }
+
+    /// First file of the game called `file_name`, e.g. a save state, the ROM or `data.json`
+    pub fn find_game_file(&self, game_name: &str, platform: Platform, file_name: &str) -> Option<PathBuf> {
+        let game_dir = platform.game_dir(game_name);
+        self.data_dirs
+            .iter()
+            .map(|dir| dir.join(&game_dir).join(file_name))
+            .find(|path| path.is_file())
+    }
+
+    /// Core info JSON describing the libretro core of the platform
+    pub fn find_core_info(&self, platform: Platform) -> Option<PathBuf> {
+        self.find_core_file(&Path::new("info").join(platform.core_info_file()))
+    }
+
+    /// Library of a libretro core like `fceumm`, named as listed in its core info
+    pub fn find_core_lib(&self, lib: &str) -> Option<PathBuf> {
+        self.find_core_file(Path::new(&Self::core_lib_name(lib)))
+    }
+
+    /// File name of a libretro core library on this operating system
+    pub fn core_lib_name(lib: &str) -> String {
+        format!("{lib}_libretro.{}", env::consts::DLL_EXTENSION)
+    }
+
+    fn find_core_file(&self, file: &Path) -> Option<PathBuf> {
+        self.core_dirs
+            .iter()
+            .map(|dir| dir.join(file))
+            .find(|path| path.is_file())
+    }
+}
+
+impl Default for AssetPaths {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
@@ -0,0 +1,40 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use retro_rust::registry::GameRegistry;
+use retro_rust::registry::rom_import::{import_roms, ImportMode};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mode = if args.iter().any(|arg| arg == "--symlink") {
+        ImportMode::Symlink
+    } else {
+        ImportMode::Copy
+    };
+    let Some(source) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        eprintln!("Usage: import_roms <rom directory or zip> [--symlink]");
+        return ExitCode::FAILURE;
+    };
+
+    let result = GameRegistry::new()
+        .and_then(|registry| import_roms(&PathBuf::from(source), &registry, mode));
+
+    match result {
+        Ok(report) => {
+            println!("Scanned {} files", report.scanned);
+            for game in &report.imported {
+                let marker = if report.playable.contains(game) { "✔" } else { "✘ (no save state)" };
+                println!("{marker} Imported {game}");
+            }
+            for (path, err) in &report.failed {
+                eprintln!("Skipped {}: {err}", path.display());
+            }
+            println!("Imported {} games, {} playable", report.imported.len(), report.playable.len());
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Import failed: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
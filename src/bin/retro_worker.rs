@@ -0,0 +1,19 @@
+use std::process::ExitCode;
+#[cfg(unix)]
+use retro_rust::environments::vec_retro_env::worker::{run_if_worker, WORKER_SOCKET_ENV};
+
+/// Worker process for `VecRetroEnv::with_worker_program`
+#[cfg(unix)]
+fn main() -> ExitCode {
+    run_if_worker();
+
+    eprintln!("retro_worker is started by VecRetroEnv, {WORKER_SOCKET_ENV} is not set");
+    ExitCode::FAILURE
+}
+
+/// `VecRetroEnv` talks to its workers over unix sockets
+#[cfg(not(unix))]
+fn main() -> ExitCode {
+    eprintln!("retro_worker is only supported on unix");
+    ExitCode::FAILURE
+}
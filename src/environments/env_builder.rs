@@ -0,0 +1,325 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::assets::AssetPaths;
+use crate::environments::image_retro_env::ImageRetroEnv;
+use crate::environments::image_retro_env::controller::ActionType;
+use crate::environments::image_retro_env::gamedata::RustRetroGameData;
+use crate::environments::image_retro_env::observation::{ObservationConfig, RamObservationConfig};
+use crate::environments::image_retro_env::platform::Platform;
+use crate::environments::movie_retro_env::MovieRetroEnv;
+use crate::error::RetroError;
+use crate::wrappers::time_limit::EpisodeLimit;
+
+/// All options of an environment in one place. Serialize it into an experiment
+/// config to reproduce the environment later:
+///
+/// ```json
+/// { "game_name": "Airstriker", "platform": "Genesis", "save_state_name": "Level1.state", "frame_skip": 2 }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvBuilder {
+    pub game_name: String,
+    pub platform: Platform,
+    pub save_state_name: String,
+    /// Scenario of the integration like `xpos`, `scenario_speedrun.json` or a path to a
+    /// JSON file, `None` uses `scenario.json`
+    #[serde(default)]
+    pub scenario: Option<String>,
+    #[serde(default)]
+    pub observation: ObservationConfig,
+    /// Observes the console memory instead of the screen, `observation` is ignored then
+    #[serde(default)]
+    pub ram_observation: Option<RamObservationConfig>,
+    #[serde(default = "default_frame_skip")]
+    pub frame_skip: u8,
+    #[serde(default)]
+    pub episode_limit: Option<EpisodeLimit>,
+    #[serde(default)]
+    pub noop_max: u32,
+    #[serde(default)]
+    pub sticky_action_prob: f64,
+    /// Number of players controlled by each action, rewards are those of the first player
+    #[serde(default = "default_players")]
+    pub players: usize,
+    #[serde(default)]
+    pub action_type: ActionType,
+    /// Ends episodes by the `done` block of the scenario, turn off to rely only on `DoneFn`s
+    #[serde(default = "default_use_scenario_done")]
+    pub use_scenario_done: bool,
+    /// Extra Lua script with custom functions, loaded after the scripts of the scenario
+    #[serde(default)]
+    pub lua_script: Option<PathBuf>,
+    /// Script function computing the reward instead of the scenario, e.g. from `lua_script`
+    #[serde(default)]
+    pub reward_function: Option<String>,
+    /// Script function ending the episode instead of the scenario
+    #[serde(default)]
+    pub done_function: Option<String>,
+    /// Records every episode as `.bk2` movie to this file, see `build_movie`
+    #[serde(default)]
+    pub record_path: Option<PathBuf>,
+    /// `None` resolves the paths from the environment variables when building
+    #[serde(default)]
+    pub paths: Option<AssetPaths>,
+}
+
+fn default_frame_skip() -> u8 {
+    4
+}
+
+fn default_players() -> usize {
+    1
+}
+
+fn default_use_scenario_done() -> bool {
+    true
+}
+
+impl EnvBuilder {
+    pub fn new(game_name: &str, platform: Platform, save_state_name: String) -> Self {
+        EnvBuilder {
+            game_name: game_name.to_string(),
+            platform,
+            save_state_name,
+            scenario: None,
+            observation: ObservationConfig::default(),
+            ram_observation: None,
+            frame_skip: default_frame_skip(),
+            episode_limit: None,
+            noop_max: 0,
+            sticky_action_prob: 0.0,
+            players: default_players(),
+            action_type: ActionType::default(),
+            use_scenario_done: default_use_scenario_done(),
+            lua_script: None,
+            reward_function: None,
+            done_function: None,
+            record_path: None,
+            paths: None,
+        }
+    }
+
+    pub fn scenario(mut self, scenario: &str) -> Self {
+        self.scenario = Some(scenario.to_string());
+        self
+    }
+
+    pub fn observation(mut self, observation: ObservationConfig) -> Self {
+        self.observation = observation;
+        self
+    }
+
+    pub fn ram_observation(mut self, ram_observation: RamObservationConfig) -> Self {
+        self.ram_observation = Some(ram_observation);
+        self
+    }
+
+    pub fn frame_skip(mut self, frame_skip: u8) -> Self {
+        self.frame_skip = frame_skip;
+        self
+    }
+
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.observation.stack_size = stack_size;
+        self
+    }
+
+    pub fn episode_limit(mut self, episode_limit: EpisodeLimit) -> Self {
+        self.episode_limit = Some(episode_limit);
+        self
+    }
+
+    pub fn noop_max(mut self, noop_max: u32) -> Self {
+        self.noop_max = noop_max;
+        self
+    }
+
+    pub fn sticky_action_prob(mut self, sticky_action_prob: f64) -> Self {
+        self.sticky_action_prob = sticky_action_prob;
+        self
+    }
+
+    pub fn players(mut self, players: usize) -> Self {
+        self.players = players;
+        self
+    }
+
+    pub fn action_type(mut self, action_type: ActionType) -> Self {
+        self.action_type = action_type;
+        self
+    }
+
+    pub fn use_scenario_done(mut self, use_scenario_done: bool) -> Self {
+        self.use_scenario_done = use_scenario_done;
+        self
+    }
+
+    pub fn lua_script(mut self, path: impl Into<PathBuf>) -> Self {
+        self.lua_script = Some(path.into());
+        self
+    }
+
+    pub fn reward_function(mut self, function: &str) -> Self {
+        self.reward_function = Some(function.to_string());
+        self
+    }
+
+    pub fn done_function(mut self, function: &str) -> Self {
+        self.done_function = Some(function.to_string());
+        self
+    }
+
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_path = Some(path.into());
+        self
+    }
+
+    pub fn paths(mut self, paths: AssetPaths) -> Self {
+        self.paths = Some(paths);
+        self
+    }
+
+    /// File name or path of the scenario, `.json` is appended to bare names
+    pub fn scenario_file(&self) -> String {
+        match self.scenario.as_deref() {
+            None => String::from("scenario.json"),
+            Some(scenario) if Path::new(scenario).extension().is_some() => scenario.to_string(),
+            Some(scenario) => format!("{scenario}.json"),
+        }
+    }
+
+    /// Scenario JSON to load. Paths with a directory like `configs/x.json` are looked up
+    /// relative to the working directory first, then like bare names in the integration
+    pub fn find_scenario(&self, paths: &AssetPaths) -> Option<PathBuf> {
+        let scenario_file = self.scenario_file();
+        let path = Path::new(&scenario_file);
+        if path.parent().is_some_and(|parent| !parent.as_os_str().is_empty()) && path.is_file() {
+            return Some(path.to_path_buf());
+        }
+        paths.find_game_file(&self.game_name, self.platform, &scenario_file)
+    }
+
+    /// Asset paths used by `build`, resolved from the environment if not set
+    pub fn resolved_paths(&self) -> AssetPaths {
+        self.paths.clone().unwrap_or_else(AssetPaths::from_env)
+    }
+
+    /// Checks the options without touching the emulator
+    pub fn validate(&self) -> Result<(), RetroError> {
+        let invalid = |message: &str| Err(RetroError::InvalidConfig(message.to_string()));
+
+        if self.frame_skip == 0 {
+            return invalid("frame_skip must be at least 1");
+        }
+        if self.observation.stack_size == 0 {
+            return invalid("stack_size must be at least 1");
+        }
+        if matches!(self.observation.resize, Some((0, _) | (_, 0))) {
+            return invalid("resize must not have a zero dimension");
+        }
+        if let Some(ram_observation) = &self.ram_observation {
+            if ram_observation.stack_size == 0 {
+                return invalid("RAM stack_size must be at least 1");
+            }
+            if matches!(ram_observation.range, Some((start, end)) if start >= end) {
+                return invalid("RAM range must not be empty");
+            }
+        }
+        if !(0.0..=1.0).contains(&self.sticky_action_prob) {
+            return invalid("sticky_action_prob must be in [0, 1]");
+        }
+        if matches!(self.episode_limit, Some(EpisodeLimit::Steps(0) | EpisodeLimit::Frames(0))) {
+            return invalid("episode_limit must be positive");
+        }
+        if self.players == 0 || self.players > RustRetroGameData::max_players() {
+            return Err(RetroError::InvalidConfig(format!(
+                "players must be between 1 and {}",
+                RustRetroGameData::max_players()
+            )));
+        }
+        if let Some(lua_script) = self.lua_script.as_ref().filter(|path| !path.is_file()) {
+            return Err(RetroError::ScriptError(format!("{} not found", lua_script.display())));
+        }
+
+        let paths = self.resolved_paths();
+        if paths.find_game(&self.game_name, self.platform).is_none() {
+            return Err(RetroError::MissingGame(PathBuf::from(self.platform.game_dir(&self.game_name))));
+        }
+        if self.find_scenario(&paths).is_none() {
+            return Err(RetroError::MissingScenario(PathBuf::from(self.scenario_file())));
+        }
+        Ok(())
+    }
+
+    pub fn build(&self) -> Result<ImageRetroEnv, RetroError> {
+        if self.record_path.is_some() {
+            return Err(RetroError::InvalidConfig(String::from("use build_movie to record movies")));
+        }
+        self.validate()?;
+        ImageRetroEnv::from_builder(self, &self.resolved_paths())
+    }
+
+    /// Builds an environment recording to `record_path`
+    pub fn build_movie(&self) -> Result<MovieRetroEnv, RetroError> {
+        let Some(record_path) = &self.record_path else {
+            return Err(RetroError::InvalidConfig(String::from("record_path is required to record movies")));
+        };
+        self.validate()?;
+        let image_env = ImageRetroEnv::from_builder(self, &self.resolved_paths())?;
+        Ok(MovieRetroEnv::with_path(image_env, record_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_roundtrip_and_validation() {
+        let json = r#"{ "game_name": "Airstriker", "platform": "Genesis", "save_state_name": "Level1.state" }"#;
+        let builder: EnvBuilder = serde_json::from_str(json).unwrap();
+        assert_eq!(builder, EnvBuilder::new("Airstriker", Platform::Genesis, String::from("Level1.state")));
+        assert!(builder.validate().is_ok());
+
+        let builder = builder.frame_skip(2).stack_size(1).episode_limit(EpisodeLimit::Steps(100));
+        let json = serde_json::to_string(&builder).unwrap();
+        assert_eq!(serde_json::from_str::<EnvBuilder>(&json).unwrap(), builder);
+
+        assert!(matches!(builder.clone().stack_size(0).validate(), Err(RetroError::InvalidConfig(_))));
+        assert!(matches!(builder.clone().sticky_action_prob(1.5).validate(), Err(RetroError::InvalidConfig(_))));
+        assert_eq!(builder.clone().scenario("xpos").scenario_file(), "xpos.json");
+        assert_eq!(builder.clone().scenario("/tmp/speedrun.json").scenario_file(), "/tmp/speedrun.json");
+        assert!(matches!(builder.clone().scenario("no_such").validate(), Err(RetroError::MissingScenario(_))));
+        assert!(matches!(builder.clone().lua_script("no_such.lua").validate(), Err(RetroError::ScriptError(_))));
+        assert!(matches!(
+            EnvBuilder::new("NoSuchGame", Platform::NES, String::new()).validate(),
+            Err(RetroError::MissingGame(_))
+        ));
+    }
+
+    #[test]
+    fn test_relative_scenario_path_from_working_directory() {
+        let builder = EnvBuilder::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+        let paths = builder.resolved_paths();
+        let scenario = paths.find_game_file("Airstriker", Platform::Genesis, "scenario.json").unwrap();
+
+        let dir = Path::new("target").join("env_builder_scenarios");
+        std::fs::create_dir_all(&dir).unwrap();
+        let relative = dir.join("speedrun.json");
+        std::fs::copy(scenario, &relative).unwrap();
+
+        let builder = builder.scenario(&relative.to_string_lossy());
+        assert!(builder.validate().is_ok());
+        assert_eq!(builder.find_scenario(&paths), Some(relative));
+        assert!(builder.clone().scenario("target/env_builder_scenarios/no_such.json").validate().is_err());
+    }
+
+    #[test]
+    fn test_players_are_limited_by_the_emulator() {
+        let builder = EnvBuilder::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+        let max_players = RustRetroGameData::max_players();
+        assert!(builder.clone().players(max_players).validate().is_ok());
+        assert!(matches!(builder.clone().players(max_players + 1).validate(), Err(RetroError::InvalidConfig(_))));
+        assert!(matches!(builder.players(0).validate(), Err(RetroError::InvalidConfig(_))));
+    }
+}
@@ -1,144 +1,641 @@
 pub mod emulator;
-mod gamedata;
-mod gamestate;
+pub(crate) mod gamedata;
+pub mod gamestate;
 mod frame_stack;
-mod controller;
+pub mod controller;
+pub mod observation;
 pub mod platform;
-
-use std::path::PathBuf;
-use image::{imageops::resize, imageops::FilterType, ImageBuffer, Luma, RgbImage};
+pub mod scenario;
+pub mod snapshot;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::assets::AssetPaths;
+use crate::environments::env_builder::EnvBuilder;
 use crate::environments::image_retro_env::controller::Controller;
 use crate::environments::image_retro_env::emulator::RustRetroEmulator;
 use crate::environments::image_retro_env::frame_stack::FrameStack;
 use crate::environments::image_retro_env::gamedata::RustRetroGameData;
 use crate::environments::image_retro_env::gamestate::GameState;
+use crate::environments::image_retro_env::observation::{Crop, ObservationConfig, RamObservationConfig};
 use crate::environments::image_retro_env::platform::Platform;
+use crate::environments::image_retro_env::scenario::{Scenario, ScriptInfo};
+use crate::environments::image_retro_env::snapshot::EnvSnapshot;
+use crate::error::RetroError;
+use crate::registry::rom_import::sha1_hex;
+use crate::traits::done_fn::{DoneContext, DoneFn, Termination};
 use crate::traits::retro_env::{RetroEnv, StepInfo};
+use crate::traits::reward_fn::{RewardContext, RewardFn};
+use crate::wrappers::time_limit::EpisodeLimit;
+
+/// Lua options of the `EnvBuilder`, applied on top of every scenario
+struct LuaOptions {
+    script: Option<PathBuf>,
+    reward_function: Option<String>,
+    done_function: Option<String>,
+}
+
+impl LuaOptions {
+    fn apply(&self, data: &RustRetroGameData) -> Result<(), RetroError> {
+        if let Some(script) = &self.script {
+            data.load_script(script)?;
+        }
+        if let Some(reward_function) = &self.reward_function {
+            data.set_reward_function(reward_function)?;
+        }
+        if let Some(done_function) = &self.done_function {
+            data.set_done_function(done_function)?;
+        }
+        Ok(())
+    }
+}
 
 pub struct ImageRetroEnv {
     pub game_name: String,
+    /// Integration directory the environment was loaded from
+    pub game_path: PathBuf,
     pub emu: RustRetroEmulator,
     data: RustRetroGameData,
     controller: Controller,
     frame_stack: FrameStack,
+    observation_config: ObservationConfig,
+    /// Crop of the `EnvBuilder`, kept when the scenario changes
+    builder_crop: Option<Crop>,
+    lua_options: LuaOptions,
+    /// Observes the console memory instead of the screen if set
+    ram_observation: Option<RamObservationConfig>,
+    pool_screen: Option<Vec<u8>>,
+    skipped_frames: u8,
+    episode_steps: u64,
+    last_button_mask: Vec<u8>,
+    rng: StdRng,
+    reward_fn: Option<Box<dyn RewardFn>>,
+    /// Variables before the current frame, only tracked for the `reward_fn`
+    previous_variables: HashMap<String, i64>,
+    done_fns: Vec<Box<dyn DoneFn>>,
+    /// Variables and rewards of the previous steps, only tracked for the `done_fns`
+    step_variables: HashMap<String, i64>,
+    episode_rewards: Vec<f32>,
     pub frame_skip: u8,
+    pub episode_limit: Option<EpisodeLimit>,
+    /// Up to this many no-op steps are taken after each reset
+    pub noop_max: u32,
+    /// Probability in `[0, 1]` to repeat the previous buttons on each emulator frame
+    pub sticky_action_prob: f64,
+    /// Ends episodes by the `done` block of the scenario, otherwise only `done_fns` do
+    pub use_scenario_done: bool,
 }
 
 impl ImageRetroEnv {
     pub fn new(game_name: &str, platform: Platform, save_state_name: String) -> Self {
-        let mut game_path: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("games");
+        Self::with_observation(game_name, platform, save_state_name, ObservationConfig::default())
+    }
+
+    pub fn with_observation(
+        game_name: &str,
+        platform: Platform,
+        save_state_name: String,
+        observation_config: ObservationConfig
+    ) -> Self {
+        Self::try_with_observation(game_name, platform, save_state_name, observation_config)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `new`, but returns an error for broken or incomplete integrations
+    pub fn try_new(game_name: &str, platform: Platform, save_state_name: String) -> Result<Self, RetroError> {
+        Self::try_with_observation(game_name, platform, save_state_name, ObservationConfig::default())
+    }
+
+    pub fn try_with_observation(
+        game_name: &str,
+        platform: Platform,
+        save_state_name: String,
+        observation_config: ObservationConfig
+    ) -> Result<Self, RetroError> {
+        Self::try_with_paths(game_name, platform, save_state_name, observation_config, &AssetPaths::from_env())
+    }
 
-        let platform_name = platform.as_str();
-        let game_dir = format!("{game_name}-{platform_name}");
-        game_path.push(game_dir);
+    /// Looks up the integration, save state, ROM and core in `paths`. The save state
+    /// may also be an absolute path.
+    pub fn try_with_paths(
+        game_name: &str,
+        platform: Platform,
+        save_state_name: String,
+        observation_config: ObservationConfig,
+        paths: &AssetPaths
+    ) -> Result<Self, RetroError> {
+        EnvBuilder::new(game_name, platform, save_state_name)
+            .observation(observation_config)
+            .paths(paths.clone())
+            .build()
+    }
+
+    /// Creates the environment of a validated `EnvBuilder`
+    pub(crate) fn from_builder(builder: &EnvBuilder, paths: &AssetPaths) -> Result<Self, RetroError> {
+        let game_name = builder.game_name.as_str();
+        let platform = builder.platform;
+        let mut observation_config = builder.observation.clone();
+
+        let game_path = paths
+            .find_game(game_name, platform)
+            .ok_or_else(|| RetroError::MissingGame(PathBuf::from(platform.game_dir(game_name))))?;
+
+        let find_file = |file_name: &str| {
+            paths
+                .find_game_file(game_name, platform, file_name)
+                .unwrap_or_else(|| game_path.join(file_name))
+        };
 
         println!("Starting environment setup...");
         println!("{}", "-".repeat(30));
-        let start_save_state = Self::create_save_state(&game_path, save_state_name);
+        let start_save_state = Self::create_save_state(find_file(&builder.save_state_name))?;
         println!("✔ Save state verified");
 
-        let emu = RustRetroEmulator::new(&platform, start_save_state);
+        let emu = RustRetroEmulator::new(&platform, start_save_state, paths)?;
         println!("✔ Emulator verified");
 
-        let mut rom_path = game_path.clone();
-        rom_path.push(platform.rom_name());
+        let rom_path = find_file(platform.rom_name());
+        Self::verify_rom(&rom_path, &find_file("rom.sha"))?;
 
-        let rom_path = rom_path
-            .to_string_lossy()
-            .to_string();
-
-        if !emu.load_rom(&rom_path) {
-            panic!("Failed to load ROM");
+        if !emu.load_rom(&rom_path.to_string_lossy()) {
+            return Err(RetroError::RomLoadFailed(rom_path));
         }
         println!("✔ Rom verified");
 
-        let data = RustRetroGameData::new(game_path.to_string_lossy().to_string());
+        let scenario_path = builder
+            .find_scenario(paths)
+            .unwrap_or_else(|| game_path.join(builder.scenario_file()));
+        let data = RustRetroGameData::new(find_file("data.json"), scenario_path)?;
         emu.configure_data(&data);
 
-        let controller = Controller::new(data.get_button_combos());
-        let frame_stack = FrameStack::new(84 * 84);
+        let lua_options = LuaOptions {
+            script: builder.lua_script.clone(),
+            reward_function: builder.reward_function.clone(),
+            done_function: builder.done_function.clone(),
+        };
+        lua_options.apply(&data)?;
+        if !data.script_info().scripts.is_empty() {
+            println!("✔ Scripts verified");
+        }
+
+        let controller = Controller::new(data.get_button_combos(), builder.action_type)
+            .with_players(builder.players);
+        let builder_crop = observation_config.crop;
+        match observation_config.crop {
+            Some(crop) => data.set_crop(crop, 0),
+            None => observation_config.crop = data.get_crop(0),
+        }
+        let ram_observation = builder.ram_observation.clone();
+        let frame_stack = match &ram_observation {
+            Some(ram_config) => FrameStack::new(
+                ram_config.frame_size(data.read_ram(ram_config.range).len()),
+                ram_config.stack_size
+            ),
+            None => {
+                let (screen_width, screen_height) = emu.get_screen_size();
+                observation_config.check_crop(screen_width as u32, screen_height as u32)?;
+                FrameStack::new(
+                    observation_config.frame_size(screen_width as u32, screen_height as u32),
+                    observation_config.stack_size
+                )
+            }
+        };
 
         println!("{}", "-".repeat(30));
         println!("Environment is ready to run!");
-        ImageRetroEnv {
+        Ok(ImageRetroEnv {
             game_name: game_name.to_string(),
+            game_path,
             emu,
             data,
             controller,
             frame_stack,
-            frame_skip: 4
+            observation_config,
+            builder_crop,
+            lua_options,
+            ram_observation,
+            pool_screen: None,
+            skipped_frames: 0,
+            episode_steps: 0,
+            last_button_mask: Vec::new(),
+            rng: StdRng::from_os_rng(),
+            reward_fn: None,
+            previous_variables: HashMap::new(),
+            done_fns: Vec::new(),
+            step_variables: HashMap::new(),
+            episode_rewards: Vec::new(),
+            frame_skip: builder.frame_skip,
+            episode_limit: builder.episode_limit,
+            noop_max: builder.noop_max,
+            sticky_action_prob: builder.sticky_action_prob,
+            use_scenario_done: builder.use_scenario_done
+        })
+    }
+
+    fn create_save_state(game_state_path: PathBuf) -> Result<GameState, RetroError> {
+        GameState::new(&game_state_path.to_string_lossy())
+            .map_err(|source| RetroError::BadStateFile { path: game_state_path, source })
+    }
+
+    /// Checks that the ROM exists and matches one of the hashes in `rom.sha`, if listed
+    fn verify_rom(rom_path: &Path, sha_path: &Path) -> Result<(), RetroError> {
+        let rom = fs::read(rom_path).map_err(|_| RetroError::MissingRom(rom_path.to_path_buf()))?;
+
+        let Ok(shas) = fs::read_to_string(sha_path) else {
+            return Ok(());
+        };
+        let expected: Vec<String> = shas
+            .lines()
+            .map(|line| line.trim().to_lowercase())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let actual = sha1_hex(&rom);
+        if expected.is_empty() || expected.contains(&actual) {
+            return Ok(());
+        }
+
+        Err(RetroError::RomHashMismatch { path: rom_path.to_path_buf(), expected, actual })
+    }
+
+    /// Buttons to press on the next emulator frame, which stick to the previous
+    /// ones with probability `sticky_action_prob`
+    pub fn sticky_button_mask(&mut self, button_bit_mask: &[u8]) -> Vec<u8> {
+        let is_sticky = self.sticky_action_prob > 0.0
+            && !self.last_button_mask.is_empty()
+            && self.rng.random_bool(self.sticky_action_prob);
+
+        if !is_sticky {
+            self.last_button_mask = button_bit_mask.to_vec();
+        }
+        self.last_button_mask.clone()
+    }
+
+    /// Writes the current emulator state as gzip'd `.state` file that loads like the
+    /// bundled ones. A bare name like `Boss` is saved as `Boss.state` into the game's
+    /// integration directory, so it is listed among its states. `.state` is appended to
+    /// every name not ending in it, so `Boss.v2` becomes `Boss.v2.state`.
+    pub fn save_state(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = path.as_ref();
+        let mut path = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => path.to_path_buf(),
+            _ => self.game_path.join(path),
+        };
+        if path.extension().is_none_or(|extension| extension != "state") {
+            path.as_mut_os_string().push(".state");
         }
+
+        let buffer = self.emu
+            .get_state()
+            .ok_or_else(|| io::Error::other("Emulator state could not be serialized"))?;
+        GameState { buffer }.save(&path)?;
+        Ok(path)
+    }
+
+    /// Captures the emulator, the scenario progress and the observation state
+    pub fn snapshot(&self) -> EnvSnapshot {
+        EnvSnapshot {
+            emulator_state: self.emu.get_state().expect("Emulator state could not be serialized"),
+            progress: self.data.progress(),
+            frame_stack: self.frame_stack.clone(),
+            pool_screen: self.pool_screen.clone(),
+            skipped_frames: self.skipped_frames,
+            episode_steps: self.episode_steps,
+            last_button_mask: self.last_button_mask.clone(),
+            rng: self.rng.clone(),
+            previous_variables: self.previous_variables.clone(),
+            step_variables: self.step_variables.clone(),
+            episode_rewards: self.episode_rewards.clone(),
+        }
+    }
+
+    /// Rewinds to a snapshot of this environment, the next step continues exactly as after
+    /// the snapshot was taken
+    pub fn restore(&mut self, snapshot: &EnvSnapshot) -> Result<(), RetroError> {
+        let same_shape = snapshot.frame_stack.stack_size() == self.frame_stack.stack_size()
+            && snapshot.frame_stack.frame_size() == self.frame_stack.frame_size();
+        if !same_shape || !self.emu.set_state(&snapshot.emulator_state) {
+            return Err(RetroError::SnapshotMismatch);
+        }
+        self.data.set_progress(&snapshot.progress);
+
+        self.frame_stack = snapshot.frame_stack.clone();
+        self.pool_screen = snapshot.pool_screen.clone();
+        self.skipped_frames = snapshot.skipped_frames;
+        self.episode_steps = snapshot.episode_steps;
+        self.last_button_mask = snapshot.last_button_mask.clone();
+        self.rng = snapshot.rng.clone();
+        self.previous_variables = snapshot.previous_variables.clone();
+        self.step_variables = snapshot.step_variables.clone();
+        self.episode_rewards = snapshot.episode_rewards.clone();
+        Ok(())
+    }
+
+    /// Replaces the scenario reward of every emulator frame, see `RewardFn`
+    pub fn set_reward_fn(&mut self, reward_fn: impl RewardFn + 'static) {
+        self.reward_fn = Some(Box::new(reward_fn));
+        self.previous_variables = self.data.lookup_all();
+    }
+
+    pub fn clear_reward_fn(&mut self) {
+        self.reward_fn = None;
+        self.previous_variables.clear();
+    }
+
+    /// Adds a termination predicate that is checked after every agent step. It ends the
+    /// episode together with the scenario, unless `use_scenario_done` is turned off.
+    pub fn add_done_fn(&mut self, done_fn: impl DoneFn + 'static) {
+        self.done_fns.push(Box::new(done_fn));
+        self.step_variables = self.data.lookup_all();
     }
 
-    fn create_save_state(game_path: &PathBuf, save_state_name: String) -> GameState {
-        let game_state_path = game_path
-            .join(save_state_name)
-            .to_string_lossy()
-            .to_string();
+    pub fn clear_done_fns(&mut self) {
+        self.done_fns.clear();
+        self.step_variables.clear();
+        self.episode_rewards.clear();
+    }
 
-        GameState::new(&game_state_path).expect("Failed to load state")
+    /// Reward of the last emulator frame, by the `reward_fn` if set
+    fn frame_reward(&mut self) -> f32 {
+        let scenario_reward = self.data.current_reward();
+        let Some(reward_fn) = self.reward_fn.as_mut() else {
+            return scenario_reward;
+        };
+
+        let current = self.data.lookup_all();
+        let context = RewardContext { current: &current, previous: &self.previous_variables, scenario_reward };
+        let reward = reward_fn.reward(&context);
+        self.previous_variables = current;
+        reward
     }
 
-    pub fn skipped_frame_step(&self, button_bit_mask: &Vec<u8>) -> f32 {
-        self.emu.set_button_mask(button_bit_mask.as_slice(), 0);
+    pub fn skipped_frame_step(&mut self, button_bit_mask: &[u8]) -> f32 {
+        let num_buttons = button_bit_mask.len() / self.controller.players();
+        for (player, player_mask) in button_bit_mask.chunks(num_buttons).enumerate() {
+            self.emu.set_button_mask(player_mask, player as u32);
+        }
         self.emu.step();
         self.data.update_ram();
 
-        self.data.current_reward()
+        // Keep the second to last frame to max-pool it with the observed one
+        self.skipped_frames += 1;
+        let observes_screen = self.ram_observation.is_none();
+        if observes_screen && self.observation_config.max_pool && self.skipped_frames + 1 == self.frame_skip {
+            self.pool_screen = self.emu.get_screen().map(|(buffer, _, _)| buffer);
+        }
+
+        self.frame_reward()
     }
 
     pub fn step_current_frame(&mut self, reward: f32) -> StepInfo {
-        let frame = self.get_screen_buffer();
+        self.skipped_frames = 0;
+        self.episode_steps += 1;
+        let frame = self.observe();
         self.frame_stack.push(frame);
 
-        StepInfo {
+        self.step_info(reward)
+    }
+
+    fn step_info(&mut self, reward: f32) -> StepInfo {
+        let info = self.data.lookup_all();
+        let frame = self.data.frame();
+        let scenario_done = self.is_done();
+        let mut terminated = self.use_scenario_done && scenario_done;
+        let mut truncated = false;
+
+        if !self.done_fns.is_empty() {
+            self.episode_rewards.push(reward);
+            let context = DoneContext {
+                current: &info,
+                previous: &self.step_variables,
+                episode_steps: self.episode_steps,
+                frame,
+                rewards: &self.episode_rewards,
+                scenario_done,
+            };
+            for done_fn in &mut self.done_fns {
+                match done_fn.check(&context) {
+                    Some(Termination::Terminated) => terminated = true,
+                    Some(Termination::Truncated) => truncated = true,
+                    None => {}
+                }
+            }
+            self.step_variables = info.clone();
+        }
+        // A failed script leaves reward and done undefined until the next reset
+        let truncated = (truncated || self.data.script_failure().is_some()) && !terminated;
+
+        let mut step_info = StepInfo {
             observation: self.frame_stack.stacked(),
             reward,
-            is_done: self.is_done(),
+            is_done: terminated || truncated,
+            terminated,
+            truncated,
+            info,
+            frame,
+            timestep: self.data.timestep(),
+        };
+        if let Some(episode_limit) = &self.episode_limit {
+            episode_limit.apply(&mut step_info, self.episode_steps);
         }
+        step_info
     }
 
     fn is_done(&self) -> bool { self.data.is_done() }
 
-    fn get_screen_buffer(&self) -> Vec<f32> {
-        let (buffer, w, h) = self
+    /// Number of no-op steps to take after the next reset
+    pub(crate) fn num_noops(&mut self) -> u32 {
+        match self.noop_max {
+            0 => 0,
+            noop_max => self.rng.random_range(0..=noop_max),
+        }
+    }
+
+    /// Step info of the first observation of an episode, which has no reward and is never done
+    pub(crate) fn first_step_info(&mut self) -> StepInfo {
+        self.episode_steps = 0;
+        let step_info = StepInfo {
+            reward: 0.0,
+            is_done: false,
+            terminated: false,
+            truncated: false,
+            ..self.step_info(0.0)
+        };
+        self.episode_rewards.clear();
+        step_info
+    }
+
+    /// Loads the start state and clears everything accumulated during the episode
+    pub(crate) fn restart(&mut self) {
+        self.emu.set_start_state();
+
+        self.emu.step();
+        self.data.reset();
+        self.data.update_ram();
+
+        self.frame_stack.clear();
+        self.pool_screen = None;
+        self.skipped_frames = 0;
+        self.episode_steps = 0;
+        self.last_button_mask.clear();
+        let frame = self.observe();
+        self.frame_stack.push(frame);
+        if self.reward_fn.is_some() {
+            self.previous_variables = self.data.lookup_all();
+        }
+        if !self.done_fns.is_empty() {
+            self.step_variables = self.data.lookup_all();
+        }
+        self.episode_rewards.clear();
+    }
+
+
+    fn observe(&mut self) -> Vec<f32> {
+        match &self.ram_observation {
+            Some(ram_config) => ram_config.process(&self.data.read_ram(ram_config.range)),
+            None => self.get_screen_buffer(),
+        }
+    }
+
+    fn get_screen_buffer(&mut self) -> Vec<f32> {
+        let (mut buffer, w, h) = self
             .emu
             .get_screen()
             .expect("Screen not available");
 
-        ImageRetroEnv::preprocess_screen(buffer, w, h)
+        if let Some(pool_screen) = self.pool_screen.take() {
+            for (pixel, pooled) in buffer.iter_mut().zip(pool_screen) {
+                *pixel = (*pixel).max(pooled);
+            }
+        }
+
+        self.observation_config.process(buffer, w as u32, h as u32)
+    }
+
+    /// Current frame as raw bytes for models taking `u8` input: the screen through every
+    /// stage of the `ObservationConfig` except normalization, in `frame_shape()`, or the
+    /// bytes of the observed RAM. It is neither stacked nor max-pooled.
+    pub fn observation_u8(&self) -> Vec<u8> {
+        match &self.ram_observation {
+            Some(ram_config) => self.data.read_ram(ram_config.range),
+            None => {
+                let (buffer, w, h) = self.emu.get_screen().expect("Screen not available");
+                self.observation_config.process_u8(buffer, w as u32, h as u32)
+            }
+        }
+    }
+
+    /// Shape of the stacked observation as `(stack_size, channels, height, width)`
+    pub fn observation_shape(&self) -> (usize, usize, usize, usize) {
+        let (channels, height, width) = self.frame_shape();
+        (self.frame_stack.stack_size(), channels, height, width)
+    }
+
+    /// Shape of a single frame as `(channels, height, width)`, `(1, 1, bytes)` for RAM observations
+    pub fn frame_shape(&self) -> (usize, usize, usize) {
+        if self.ram_observation.is_some() {
+            return (1, 1, self.frame_stack.frame_size());
+        }
+        let (screen_width, screen_height) = self.emu.get_screen_size();
+        let (w, h) = self.observation_config.output_size(screen_width as u32, screen_height as u32);
+        (self.observation_config.channels(), h as usize, w as usize)
+    }
+
+    /// Current value of a variable of `data.json`, e.g. `lives`
+    pub fn get_var(&self, name: &str) -> Option<i64> {
+        self.data.lookup_value(name)
+    }
+
+    /// Change of a variable during the last emulator frame
+    pub fn get_var_delta(&self, name: &str) -> Option<i64> {
+        self.data.lookup_delta(name)
+    }
+
+    /// Current values of all variables of `data.json`
+    pub fn variables(&self) -> HashMap<String, i64> {
+        self.data.lookup_all()
     }
 
-    fn preprocess_screen(buffer: Vec<u8>, w: i32, h: i32) -> Vec<f32> {
-        // 1. Convert buffer -> ImageBuffer
-        let img: RgbImage = ImageBuffer::from_raw(w as u32, h as u32, buffer)
-            .expect("Failed to convert screen buffer to image");
+    /// Writes a variable into the emulator memory, e.g. for infinite lives. The write
+    /// does not count as a change for rewards or done conditions measuring deltas.
+    pub fn set_var(&mut self, name: &str, value: i64) -> Result<(), RetroError> {
+        if self.data.set_value(name, value) {
+            for variables in [&mut self.previous_variables, &mut self.step_variables] {
+                if let Some(previous) = variables.get_mut(name) {
+                    *previous = value;
+                }
+            }
+            Ok(())
+        } else {
+            Err(RetroError::UnknownVariable(name.to_string()))
+        }
+    }
 
-        // 2. Resize to smaller dimensions, e.g., 84x84
-        let resized = resize(&img, 84, 84, FilterType::Nearest);
+    /// Replaces the rewards, done conditions, actions and crop of the running environment.
+    /// Its scripts are looked up in `game_path`. The crop, Lua script and Lua functions set
+    /// on the `EnvBuilder` are applied on top of the new scenario again. If
+    /// the crop changes the size of the observation, the frame stack starts over. The reward
+    /// and done accumulators start from zero, reset to begin a clean episode.
+    pub fn set_scenario(&mut self, scenario: &Scenario) -> Result<(), RetroError> {
+        let variables = self.data.lookup_all();
+        scenario.validate(variables.keys().map(String::as_str))?;
+
+        let scenario_crop = scenario.player_crop(0)
+            .map(|[x, y, width, height]| Crop { x, y, width, height })
+            .filter(|crop| !crop.is_empty());
+        let observation_config = ObservationConfig {
+            crop: self.builder_crop.or(scenario_crop),
+            ..self.observation_config.clone()
+        };
+        let (screen_width, screen_height) = self.emu.get_screen_size();
+        let (screen_width, screen_height) = (screen_width as u32, screen_height as u32);
+        if self.ram_observation.is_none() {
+            observation_config.check_crop(screen_width, screen_height)?;
+        }
 
-        // 3. Optional: convert to grayscale
-        let gray: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::from_fn(resized.width(), resized.height(), |x, y| {
-            let pixel = resized.get_pixel(x, y);
-            // Standard grayscale: 0.299 R + 0.587 G + 0.114 B
-            let gray_val = (0.299 * pixel[0] as f32
-                + 0.587 * pixel[1] as f32
-                + 0.114 * pixel[2] as f32) as u8;
-            Luma([gray_val])
-        });
+        self.data.load_scenario(scenario, &self.game_path)?;
+        if let Some(crop) = self.builder_crop {
+            self.data.set_crop(crop, 0);
+        }
+        self.lua_options.apply(&self.data)?;
+        self.controller = Controller::new(self.data.get_button_combos(), self.controller.action_type)
+            .with_players(self.controller.players());
 
-        // 4. Flatten to Vec<f32> and normalize
-        gray.pixels()
-            .map(|p| p[0] as f32 / 255.0)
-            .collect()
+        let frame_size = observation_config.frame_size(screen_width, screen_height);
+        if self.ram_observation.is_none() && frame_size != self.frame_stack.frame_size() {
+            self.frame_stack = FrameStack::new(frame_size, self.frame_stack.stack_size());
+        }
+        self.observation_config = observation_config;
+        Ok(())
     }
 
+    /// Runtime error of a reward or done script since the last reset. Steps after it are
+    /// reported as truncated, as their reward and done flag are undefined.
+    pub fn script_error(&self) -> Option<RetroError> {
+        self.data.script_failure()
+    }
+
+    /// Loaded scripts and the functions computing reward and done
+    pub fn scripts(&self) -> ScriptInfo {
+        self.data.script_info()
+    }
+
+    /// Total scenario reward of the episode, not affected by the `reward_fn`
     pub fn episode_reward(&self) -> f32 {
         self.data.total_reward()
     }
 
-    pub fn get_button_bitmask(&self, action: usize) -> &Vec<u8> {
-        self.controller.get_button_bitmask(action)
+    /// Buttons of all players for the action, one equally long mask per player
+    pub fn get_button_bitmask(&self, action: usize) -> Vec<u8> {
+        self.controller.button_masks(action)
+    }
+
+    /// Number of players controlled by each action
+    pub fn players(&self) -> usize {
+        self.controller.players()
     }
 }
 
@@ -148,29 +645,41 @@ impl RetroEnv for ImageRetroEnv {
 
         let mut reward = 0.0;
         for _ in 0..self.frame_skip {
-            reward += self.skipped_frame_step(button_bit_mask)
+            let frame_button_mask = self.sticky_button_mask(&button_bit_mask);
+            reward += self.skipped_frame_step(&frame_button_mask)
         }
 
         self.step_current_frame(reward)
     }
 
     fn reset(&mut self) -> StepInfo {
-        self.emu.set_start_state();
-
-        self.emu.step();
-        self.data.reset();
-        self.data.update_ram();
-
-        self.frame_stack.clear();
-        let frame = self.get_screen_buffer();
-        self.frame_stack.push(frame);
-
-        StepInfo {
-            observation: self.frame_stack.stacked(),
-            reward: self.data.current_reward(),
-            is_done: self.is_done()
+        self.restart();
+
+        // Action 0 presses no buttons. Noops ending the episode are dropped by starting over.
+        for _ in 0..self.num_noops() {
+            if self.step(0).is_done {
+                self.restart();
+                break;
+            }
         }
+        self.first_step_info()
+    }
+
+    /// Seeds the random number generator used for no-op starts and sticky actions
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
     }
 
     fn num_actions(&self) -> usize { self.controller.num_actions }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_new_reports_missing_game() {
+        let result = ImageRetroEnv::try_new("NoSuchGame", Platform::NES, String::from("Level1.state"));
+        assert!(matches!(result, Err(RetroError::MissingGame(_))));
+    }
+}
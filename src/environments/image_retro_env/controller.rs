@@ -1,12 +1,28 @@
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// How a `usize` action is mapped to controller buttons
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActionType {
+    /// One action per combination of the button groups of the scenario
+    #[default]
+    Discrete,
+    /// Bit `i` of the action presses button `i`, any combination is allowed
+    MultiBinary,
+}
 
 pub struct Controller {
     actions_to_buttons_bitmask_map: HashMap<usize, Vec<u8>>,
+    /// Actions of all players together
     pub num_actions: usize,
+    pub action_type: ActionType,
+    /// Actions of a single player
+    player_actions: usize,
+    players: usize,
 }
 
 impl Controller {
-    pub fn new(button_combos: Vec<Vec<u64>>) -> Self {
+    pub fn new(button_combos: Vec<Vec<u64>>, action_type: ActionType) -> Self {
         let max_button_value = button_combos.clone().iter()
             .flat_map(|inner| inner.iter())
             .copied()
@@ -14,17 +30,41 @@ impl Controller {
 
         let num_buttons = 64 - max_button_value.leading_zeros() as usize;
 
-        let mut num_actions = 1;
-        for combo in &button_combos {
-            num_actions *= combo.len();
-        }
+        let num_actions = match action_type {
+            ActionType::Discrete => button_combos.iter().map(|combo| combo.len()).product(),
+            ActionType::MultiBinary => 1 << num_buttons,
+        };
 
         let actions_to_buttons_bitmask_map: HashMap<usize, Vec<u8>> =
             (0..num_actions)
-                .map(|i| (i, Self::compute_button_bitmask(i, &button_combos, num_buttons)))
+                .map(|i| {
+                    let bitmask = match action_type {
+                        ActionType::Discrete => Self::compute_button_bitmask(i, &button_combos, num_buttons),
+                        ActionType::MultiBinary => Self::bits(i as u64, num_buttons),
+                    };
+                    (i, bitmask)
+                })
                 .collect();
 
-        Controller { actions_to_buttons_bitmask_map, num_actions }
+        Controller {
+            actions_to_buttons_bitmask_map,
+            num_actions,
+            action_type,
+            player_actions: num_actions,
+            players: 1,
+        }
+    }
+
+    /// Lets one action control `players` players, like gym-retro the action is decoded into
+    /// one single player action per player, player 1 in the lowest digit
+    pub fn with_players(mut self, players: usize) -> Self {
+        self.players = players;
+        self.num_actions = self.player_actions.pow(players as u32);
+        self
+    }
+
+    pub fn players(&self) -> usize {
+        self.players
     }
 
     fn compute_button_bitmask(
@@ -38,16 +78,29 @@ impl Controller {
             action /= combo.len();
             buttons_value |= combo[current];
         }
-        let mut buttons_bitmask = vec![0u8; num_buttons];
-        for i in 0..num_buttons {
-            buttons_bitmask[i] = ((buttons_value >> i) & 1) as u8;
-        }
-        buttons_bitmask
+        Self::bits(buttons_value, num_buttons)
     }
 
+    fn bits(buttons_value: u64, num_buttons: usize) -> Vec<u8> {
+        (0..num_buttons)
+            .map(|i| ((buttons_value >> i) & 1) as u8)
+            .collect()
+    }
+
+    /// Buttons of a single player's action
     pub fn get_button_bitmask(&self, action: usize) -> &Vec<u8> {
         self.actions_to_buttons_bitmask_map.get(&action).unwrap()
     }
+
+    /// Buttons of all players for an action of `num_actions`, one equally long mask per player
+    pub fn button_masks(&self, mut action: usize) -> Vec<u8> {
+        let mut masks = Vec::new();
+        for _ in 0..self.players {
+            masks.extend_from_slice(self.get_button_bitmask(action % self.player_actions));
+            action /= self.player_actions;
+        }
+        masks
+    }
 }
 
 #[cfg(test)]
@@ -55,6 +108,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::vec_init_then_push)]
     fn test_get_button_bitmask() {
         let mut button_combos = Vec::new();
         button_combos.push(vec![0, 16, 32]);
@@ -75,11 +129,25 @@ mod tests {
             2304,
             2560,
         ]);
-        let controller = Controller::new(button_combos);
+        let controller = Controller::new(button_combos.clone(), ActionType::Discrete);
 
-        assert_eq!(controller.get_button_bitmask(0), vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
-        assert_eq!(controller.get_button_bitmask(31), vec![1, 1, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0]);
-        assert_eq!(controller.get_button_bitmask(82), vec![0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0]);
-        assert_eq!(controller.get_button_bitmask(125), vec![0, 0, 0, 0, 0, 1, 0, 1, 0, 1, 0, 1]);
+        assert_eq!(*controller.get_button_bitmask(0), vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(*controller.get_button_bitmask(31), vec![1, 1, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(*controller.get_button_bitmask(82), vec![0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(*controller.get_button_bitmask(125), vec![0, 0, 0, 0, 0, 1, 0, 1, 0, 1, 0, 1]);
+
+        let controller = Controller::new(button_combos, ActionType::MultiBinary);
+        assert_eq!(controller.num_actions, 4096);
+        assert_eq!(*controller.get_button_bitmask(0b1000_0000_0101), vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_button_masks_of_two_players() {
+        let controller = Controller::new(vec![vec![0, 1, 2]], ActionType::Discrete).with_players(2);
+        assert_eq!(controller.num_actions, 9);
+        assert_eq!(controller.players(), 2);
+        // Player 1 presses the second button, player 2 the first one
+        assert_eq!(controller.button_masks(2 + 3), vec![0, 1, 1, 0]);
+        assert_eq!(controller.button_masks(0), vec![0, 0, 0, 0]);
     }
 }
@@ -1,10 +1,17 @@
+use std::env;
 use std::ffi::CString;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use crate::assets::AssetPaths;
 use crate::environments::image_retro_env::gamedata::RetroGameData;
 use crate::environments::image_retro_env::gamedata::RustRetroGameData;
 use crate::environments::image_retro_env::gamestate::GameState;
 use crate::environments::image_retro_env::platform::Platform;
+use crate::error::RetroError;
+
+/// Variable the C++ emulator reads its single core directory from, distinct from the user-facing
+/// search list in `assets::CORE_PATH_ENV`
+const CPP_CORE_PATH_ENV: &str = "RETRO_CORE_PATH";
 
 #[repr(C)]
 pub struct RetroEmulator {
@@ -13,6 +20,7 @@ pub struct RetroEmulator {
 
 unsafe extern "C" {
     fn emulator_new() -> *mut RetroEmulator;
+    fn emulator_free(emulator: *mut RetroEmulator);
     fn emulator_configure_data(
         emulator: *mut RetroEmulator,
         data: *mut RetroGameData,
@@ -20,6 +28,8 @@ unsafe extern "C" {
     fn emulator_load_rom(emulator: *mut RetroEmulator, rom_path: *const std::os::raw::c_char) -> bool;
     fn emulator_run(emulator: *mut RetroEmulator);
     fn emulator_set_state(emulator: *mut RetroEmulator, state_data: *const u8, size: usize) -> bool;
+    fn emulator_get_state_size(emulator: *mut RetroEmulator) -> usize;
+    fn emulator_get_state(emulator: *mut RetroEmulator, state_data: *mut u8, size: usize) -> bool;
     fn emulator_get_screen_width(emulator: *mut RetroEmulator) -> i32;
     fn emulator_get_screen_height(emulator: *mut RetroEmulator) -> i32;
     fn emulator_get_screen(
@@ -44,26 +54,33 @@ pub struct RustRetroEmulator {
 }
 
 impl RustRetroEmulator {
-    pub fn new(platform: &Platform, start_game_state: GameState) -> Self {
-        let cores_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("cores");
-
-        let platform_name = platform.as_str().to_lowercase();
+    pub fn new(platform: &Platform, start_game_state: GameState, paths: &AssetPaths) -> Result<Self, RetroError> {
+        let missing_core = |path: PathBuf| RetroError::MissingCore { platform: *platform, path };
+        let info_file = Path::new("info").join(platform.core_info_file());
 
-        let json_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("cores")
-            .join("info")
-            .join(format!("{platform_name}.json"));
-        let json_str = fs::read_to_string(&json_path).unwrap();
+        let json_path = paths.find_core_info(*platform).ok_or_else(|| missing_core(info_file.clone()))?;
+        let json_str = fs::read_to_string(&json_path).map_err(|_| missing_core(json_path.clone()))?;
+        let core_lib = Self::core_lib(platform, &json_str).ok_or_else(|| missing_core(json_path.clone()))?;
+        let core_lib_path = paths
+            .find_core_lib(&core_lib)
+            .ok_or_else(|| missing_core(PathBuf::from(AssetPaths::core_lib_name(&core_lib))))?;
+        // The C++ side only looks for the library in a single directory
+        let cores_path = core_lib_path.parent().map(Path::to_path_buf).unwrap_or_default();
 
-        let json_str_c = CString::new(json_str).expect("CString::new failed");
+        let json_str_c = CString::new(json_str).map_err(|_| missing_core(json_path))?;
         unsafe {
-            std::env::set_var("RETRO_CORE_PATH", cores_path);
+            env::set_var(CPP_CORE_PATH_ENV, cores_path);
             load_core_info(json_str_c.as_ptr());
             let retro_emulator = emulator_new();
-            RustRetroEmulator { retro_emulator, start_game_state }
+            Ok(RustRetroEmulator { retro_emulator, start_game_state })
         }
     }
+
+    /// Name of the libretro core library listed for the platform in its core info JSON
+    fn core_lib(platform: &Platform, core_info: &str) -> Option<String> {
+        let core_info: serde_json::Value = serde_json::from_str(core_info).ok()?;
+        core_info[platform.as_str()]["lib"].as_str().map(String::from)
+    }
     pub fn configure_data(&self, data: &RustRetroGameData) {
         unsafe {
             emulator_configure_data(self.retro_emulator, data.retro_data);
@@ -74,6 +91,14 @@ impl RustRetroEmulator {
             emulator_run(self.retro_emulator)
         }
     }
+    pub fn get_screen_size(&self) -> (i32, i32) {
+        unsafe {
+            (
+                emulator_get_screen_width(self.retro_emulator),
+                emulator_get_screen_height(self.retro_emulator)
+            )
+        }
+    }
     pub fn get_screen(&self) -> Option<(Vec<u8>, i32, i32)> {
         unsafe {
             let w = emulator_get_screen_width(self.retro_emulator);
@@ -82,7 +107,7 @@ impl RustRetroEmulator {
             let mut buffer = vec![0u8; (w * h * 3) as usize];
 
             let ok = emulator_get_screen(self.retro_emulator, w, h, buffer.as_mut_ptr());
-            ok.then(|| (buffer, w, h))
+            ok.then_some((buffer, w, h))
         }
     }
     pub fn set_button_mask(&self, mask: &[u8], player: u32) {
@@ -102,12 +127,30 @@ impl RustRetroEmulator {
         }
     }
     pub fn set_start_state(&self) -> bool {
+        self.set_state(&self.start_game_state.buffer)
+    }
+    pub fn set_state(&self, state: &[u8]) -> bool {
         unsafe {
-            emulator_set_state(
-                self.retro_emulator,
-                self.start_game_state.buffer.as_ptr(),
-                self.start_game_state.buffer.len()
-            )
+            emulator_set_state(self.retro_emulator, state.as_ptr(), state.len())
         }
     }
-}
\ No newline at end of file
+    /// Serialized state of the running core, in the format of uncompressed `.state` files
+    pub fn get_state(&self) -> Option<Vec<u8>> {
+        unsafe {
+            let size = emulator_get_state_size(self.retro_emulator);
+            let mut state = vec![0u8; size];
+
+            let ok = emulator_get_state(self.retro_emulator, state.as_mut_ptr(), size);
+            ok.then_some(state)
+        }
+    }
+}
+
+impl Drop for RustRetroEmulator {
+    /// Unloads the ROM and the libretro core
+    fn drop(&mut self) {
+        unsafe {
+            emulator_free(self.retro_emulator);
+        }
+    }
+}
@@ -1,71 +1,75 @@
-use arraydeque::{ArrayDeque, Wrapping};
-
-const STACK_SIZE: usize = 4;
+use std::collections::VecDeque;
 
+#[derive(Clone)]
 pub struct FrameStack {
-    frames: ArrayDeque<Vec<f32>, STACK_SIZE, Wrapping>,
+    frames: VecDeque<Vec<f32>>,
     frame_size: usize,
+    stack_size: usize,
 }
 
 impl FrameStack {
-    pub fn new(frame_size: usize) -> Self {
-        let zero_frame = vec![0.0; frame_size];
+    pub fn new(frame_size: usize, stack_size: usize) -> Self {
+        assert!(stack_size > 0, "Frame stack needs to hold at least one frame");
 
-        let mut frames = ArrayDeque::new();
-        for _ in 0..STACK_SIZE {
-            ArrayDeque::<Vec<f32>, STACK_SIZE, Wrapping>::push_back(
-                &mut frames,
-                zero_frame.clone()
-            );
-        }
+        let frames = VecDeque::from(vec![vec![0.0; frame_size]; stack_size]);
 
-        Self { frames, frame_size }
+        Self { frames, frame_size, stack_size }
     }
 }
 
 impl FrameStack {
     pub fn push(&mut self, frame: Vec<f32>) {
+        if self.frames.len() == self.stack_size {
+            self.frames.pop_front();
+        }
         self.frames.push_back(frame);
     }
 
+    /// Concatenates the frames from oldest to newest
     pub fn stacked(&self) -> Vec<f32> {
-        let mut out = Vec::with_capacity(self.frames.len() * self.frame_size);
-
-        let n = self.frames.len();
+        let mut out = Vec::with_capacity(self.stack_size * self.frame_size);
 
-        for (i, frame) in self.frames.iter().enumerate() {
-            if i == n - 1 && n >= 2 {
-                out.extend_from_slice(&self.max_last_two());
-            } else {
-                out.extend_from_slice(frame);
-            }
+        for frame in &self.frames {
+            out.extend_from_slice(frame);
         }
         out
     }
 
     pub fn clear(&mut self) {
-        let zero_frame = vec![0.0; self.frame_size];
-
-        for _ in 0..STACK_SIZE {
-            self.push(zero_frame.clone());
+        for frame in self.frames.iter_mut() {
+            frame.fill(0.0);
         }
     }
 
-    /// Returns the elementwise max over the last 2 frames
-    fn max_last_two(&self) -> Vec<f32> {
-        // Make sure we have at least 2 frames
-        if self.frames.len() < 2 {
-            return self.frames.back().cloned().unwrap_or_else(|| vec![0.0; self.frame_size]);
-        }
-
-        let last = self.frames.get(self.frames.len() - 1).unwrap();
-        let second_last = self.frames.get(self.frames.len() - 2).unwrap();
+    pub fn stack_size(&self) -> usize {
+        self.stack_size
+    }
 
-        last.iter()
-            .zip(second_last.iter())
-            .map(|(a, b)| a.max(*b))
-            .collect()
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stacked_keeps_newest_frames() {
+        let mut frame_stack = FrameStack::new(2, 3);
+        assert_eq!(frame_stack.stacked(), vec![0.0; 6]);
+
+        for i in 1..=4 {
+            frame_stack.push(vec![i as f32; 2]);
+        }
+        assert_eq!(frame_stack.stacked(), vec![2.0, 2.0, 3.0, 3.0, 4.0, 4.0]);
 
+        frame_stack.clear();
+        frame_stack.push(vec![5.0; 2]);
+        assert_eq!(frame_stack.stacked(), vec![0.0, 0.0, 0.0, 0.0, 5.0, 5.0]);
+
+        let mut single = FrameStack::new(2, 1);
+        single.push(vec![1.0; 2]);
+        assert_eq!(single.stacked(), vec![1.0, 1.0]);
+    }
+}
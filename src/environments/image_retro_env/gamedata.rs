@@ -1,6 +1,10 @@
-use std::ffi::CString;
-use std::path::Path;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
 use std::os::raw::c_char;
+use crate::environments::image_retro_env::observation::Crop;
+use crate::environments::image_retro_env::scenario::{Scenario, ScriptInfo};
+use crate::error::RetroError;
 
 #[repr(C)]
 pub struct RetroGameData {
@@ -14,12 +18,35 @@ struct RetroActionSet {
     num_values: usize,
 }
 
+#[repr(C)]
+struct RetroVariable {
+    name: *mut c_char,
+    value: i64,
+}
+
+#[repr(C)]
+struct RetroMemoryBlock {
+    offset: usize,
+    size: usize,
+    data: *const u8,
+}
+
 unsafe extern "C" {
     fn gamedata_new() -> *mut RetroGameData;
-    fn gamedata_load(
+    fn gamedata_free(gamedata: *mut RetroGameData);
+    fn gamedata_load_data(gamedata: *mut RetroGameData, data_path: *const c_char) -> bool;
+    fn gamedata_load_scenario(
+        gamedata: *mut RetroGameData,
+        scenario_path: *const c_char,
+        scripts: *const *const c_char,
+        num_scripts: usize
+    ) -> bool;
+    fn gamedata_load_scenario_json(
         gamedata: *mut RetroGameData,
-        data_path: *const c_char,
-        scenario_path: *const c_char
+        scenario_json: *const c_char,
+        base_dir: *const c_char,
+        scripts: *const *const c_char,
+        num_scripts: usize
     ) -> bool;
     fn gamedata_reset(gamedata: *mut RetroGameData);
     fn gamedata_valid_actions(
@@ -30,10 +57,77 @@ unsafe extern "C" {
         actions: *mut RetroActionSet,
         num_entries: usize,
     );
+    fn gamedata_lookup_all(
+        gamedata: *mut RetroGameData,
+        num_entries: *mut usize
+    ) -> *mut RetroVariable;
+    fn gamedata_free_variables(
+        variables: *mut RetroVariable,
+        num_entries: usize,
+    );
+    fn gamedata_memory_blocks(
+        gamedata: *mut RetroGameData,
+        num_blocks: *mut usize
+    ) -> *mut RetroMemoryBlock;
+    fn gamedata_free_memory_blocks(blocks: *mut RetroMemoryBlock);
+    fn gamedata_scripts(gamedata: *mut RetroGameData, num_scripts: *mut usize) -> *mut *mut c_char;
+    fn gamedata_free_strings(strings: *mut *mut c_char, num_strings: usize);
+    fn gamedata_reward_function(gamedata: *mut RetroGameData) -> *mut c_char;
+    fn gamedata_done_function(gamedata: *mut RetroGameData) -> *mut c_char;
+    fn gamedata_free_string(string: *mut c_char);
+    fn gamedata_load_script(gamedata: *mut RetroGameData, script_path: *const c_char, scope: *const c_char) -> bool;
+    fn gamedata_set_reward_function(gamedata: *mut RetroGameData, function: *const c_char) -> bool;
+    fn gamedata_set_done_function(gamedata: *mut RetroGameData, function: *const c_char) -> bool;
+    fn gamedata_script_error(gamedata: *mut RetroGameData) -> *const c_char;
+    fn gamedata_lookup_value(gamedata: *mut RetroGameData, name: *const c_char, value: *mut i64) -> bool;
+    fn gamedata_lookup_delta(gamedata: *mut RetroGameData, name: *const c_char, delta: *mut i64) -> bool;
+    fn gamedata_set_value(gamedata: *mut RetroGameData, name: *const c_char, value: i64) -> bool;
     fn gamedata_update_ram(gamedata: *mut RetroGameData);
     fn gamedata_current_reward(gamedata: *mut RetroGameData) -> f32;
     fn gamedata_total_reward(gamedata: *mut RetroGameData) -> f32;
     fn gamedata_is_done(gamedata: *mut RetroGameData) -> bool;
+    fn gamedata_frame(gamedata: *mut RetroGameData) -> u64;
+    fn gamedata_timestep(gamedata: *mut RetroGameData) -> u64;
+    fn gamedata_get_crop(
+        gamedata: *mut RetroGameData,
+        x: *mut usize,
+        y: *mut usize,
+        width: *mut usize,
+        height: *mut usize,
+        player: u32,
+    );
+    fn gamedata_set_crop(
+        gamedata: *mut RetroGameData,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        player: u32,
+    );
+    fn gamedata_max_players() -> usize;
+    fn gamedata_get_progress(
+        gamedata: *mut RetroGameData,
+        rewards: *mut f32,
+        total_rewards: *mut f32,
+        done: *mut bool,
+        frame: *mut u64,
+    );
+    fn gamedata_set_progress(
+        gamedata: *mut RetroGameData,
+        rewards: *const f32,
+        total_rewards: *const f32,
+        done: bool,
+        frame: u64,
+    );
+}
+
+/// Reward and done accumulators of the scenario, one reward entry per player
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioProgress {
+    rewards: Vec<f32>,
+    total_rewards: Vec<f32>,
+    done: bool,
+    frame: u64,
 }
 
 #[derive(Debug)]
@@ -42,33 +136,149 @@ pub struct RustRetroGameData {
 }
 
 impl RustRetroGameData {
-    pub fn new(game_path: String) -> Self {
-        unsafe {
-            let retro_data = gamedata_new();
-            let data_path = game_path.clone() + "/data.json";
-            let data_path = Path::new(&data_path)
-                .canonicalize()
-                .expect("Data path not found");
+    pub fn new(data_path: PathBuf, scenario_path: PathBuf) -> Result<Self, RetroError> {
+        // Owned right away, so the handle is freed if loading fails
+        let data = RustRetroGameData { retro_data: unsafe { gamedata_new() } };
 
-            let data_path = CString::new(data_path.to_str().unwrap()).expect("CString::new failed");
+        let data_path_c = Self::c_path(&data_path)
+            .ok_or_else(|| RetroError::InvalidDataJson(data_path.clone()))?;
+        if !unsafe { gamedata_load_data(data.retro_data, data_path_c.as_ptr()) } {
+            return Err(RetroError::InvalidDataJson(data_path));
+        }
 
-            let scenario_path = game_path.clone() + "/scenario.json";
-            let scenario_path = Path::new(&scenario_path)
-                .canonicalize()
-                .expect("Scenario path not found");
+        let scenario_path_c = Self::c_path(&scenario_path)
+            .ok_or_else(|| RetroError::InvalidScenario(scenario_path.clone()))?;
+        // Files gym-retro reads but the typed model does not are loaded without the script check
+        let scripts = Scenario::load(&scenario_path).map(|scenario| scenario.scripts).unwrap_or_default();
+        let scripts_c = Self::c_strings(&scripts);
+        let script_ptrs: Vec<*const c_char> = scripts_c.iter().map(|script| script.as_ptr()).collect();
+        if !unsafe {
+            gamedata_load_scenario(data.retro_data, scenario_path_c.as_ptr(), script_ptrs.as_ptr(), script_ptrs.len())
+        } {
+            return Err(data.script_failure().unwrap_or(RetroError::InvalidScenario(scenario_path)));
+        }
+        if let Ok(scenario) = Scenario::load(&scenario_path) {
+            data.set_player_crops(&scenario);
+        }
 
-            let scenario_path = CString::new(scenario_path.to_str().unwrap()).expect("CString::new failed");
+        Ok(data)
+    }
 
-            if !gamedata_load(retro_data, data_path.as_ptr(), scenario_path.as_ptr()) {
-                panic!("Failed to load game data");
-            }
+    /// Replaces the scenario, its scripts are looked up in `base_dir`. The old scenario is
+    /// kept if the new one fails to load.
+    pub fn load_scenario(&self, scenario: &Scenario, base_dir: &Path) -> Result<(), RetroError> {
+        let rejected = || RetroError::InvalidScenarioSpec(String::from("rejected by gym-retro"));
+        // gym-retro misreads `crops`, they are set per player once the scenario is loaded
+        let without_crops = Scenario { crops: Vec::new(), ..scenario.clone() };
+        let json = serde_json::to_string(&without_crops)
+            .map_err(|err| RetroError::InvalidScenarioSpec(err.to_string()))?;
+        let (Ok(scenario_json), Some(base_dir)) = (CString::new(json), Self::c_path(base_dir)) else {
+            return Err(rejected());
+        };
+        let scripts_c = Self::c_strings(&scenario.scripts);
+        let script_ptrs: Vec<*const c_char> = scripts_c.iter().map(|script| script.as_ptr()).collect();
+        if unsafe {
+            gamedata_load_scenario_json(
+                self.retro_data,
+                scenario_json.as_ptr(),
+                base_dir.as_ptr(),
+                script_ptrs.as_ptr(),
+                script_ptrs.len()
+            )
+        } {
+            self.set_player_crops(scenario);
+            Ok(())
+        } else {
+            Err(self.script_failure().unwrap_or_else(rejected))
+        }
+    }
 
-            RustRetroGameData {
-                retro_data
+    /// Loads a script next to the ones of the scenario, its extension selects the language
+    pub fn load_script(&self, script_path: &Path) -> Result<(), RetroError> {
+        let not_found = || RetroError::ScriptError(format!("{} not found", script_path.display()));
+        let path = Self::c_path(script_path).ok_or_else(not_found)?;
+        let scope = script_path.extension().unwrap_or_default().to_string_lossy().to_string();
+        let scope = CString::new(scope).map_err(|_| not_found())?;
+        if unsafe { gamedata_load_script(self.retro_data, path.as_ptr(), scope.as_ptr()) } {
+            Ok(())
+        } else {
+            Err(self.script_failure().unwrap_or_else(not_found))
+        }
+    }
+
+    /// Computes the reward by a script function, optionally prefixed with its scope like `lua:reward`
+    pub fn set_reward_function(&self, function: &str) -> Result<(), RetroError> {
+        self.set_function(function, gamedata_set_reward_function)
+    }
+
+    pub fn set_done_function(&self, function: &str) -> Result<(), RetroError> {
+        self.set_function(function, gamedata_set_done_function)
+    }
+
+    fn set_function(
+        &self,
+        function: &str,
+        set: unsafe extern "C" fn(*mut RetroGameData, *const c_char) -> bool
+    ) -> Result<(), RetroError> {
+        let undefined = || RetroError::ScriptError(format!("Lua function {function} is not defined"));
+        let function_c = CString::new(function).map_err(|_| undefined())?;
+        if unsafe { set(self.retro_data, function_c.as_ptr()) } {
+            Ok(())
+        } else {
+            Err(self.script_failure().unwrap_or_else(undefined))
+        }
+    }
+
+    /// Error of the last script load or of a script function since the last reset
+    pub fn script_failure(&self) -> Option<RetroError> {
+        unsafe {
+            let error = gamedata_script_error(self.retro_data);
+            (!error.is_null()).then(|| {
+                RetroError::ScriptError(CStr::from_ptr(error).to_string_lossy().to_string())
+            })
+        }
+    }
+
+    pub fn script_info(&self) -> ScriptInfo {
+        unsafe {
+            let mut n: usize = 0;
+            let ptr = gamedata_scripts(self.retro_data, &mut n);
+            let scripts = (0..n)
+                .map(|i| CStr::from_ptr(*ptr.add(i)).to_string_lossy().to_string())
+                .collect();
+            gamedata_free_strings(ptr, n);
+
+            ScriptInfo {
+                scripts,
+                reward_function: Self::take_string(gamedata_reward_function(self.retro_data)),
+                done_function: Self::take_string(gamedata_done_function(self.retro_data)),
             }
         }
     }
 
+    /// Copies and frees a string allocated by the wrapper
+    unsafe fn take_string(string: *mut c_char) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        unsafe {
+            let owned = CStr::from_ptr(string).to_string_lossy().to_string();
+            gamedata_free_string(string);
+            Some(owned)
+        }
+    }
+
+    /// Strings with interior NUL bytes are left out, gym-retro could not open such files anyway
+    fn c_strings(strings: &[String]) -> Vec<CString> {
+        strings.iter().filter_map(|string| CString::new(string.as_str()).ok()).collect()
+    }
+
+    /// Absolute path as C string, `None` if the file does not exist
+    fn c_path(path: &Path) -> Option<CString> {
+        let path = path.canonicalize().ok()?;
+        CString::new(path.to_str()?).ok()
+    }
+
     pub fn get_button_combos(&self) -> Vec<Vec<u64>> {
         unsafe {
             let mut n: usize = 0;
@@ -125,4 +335,167 @@ impl RustRetroGameData {
             gamedata_is_done(self.retro_data)
         }
     }
-}
\ No newline at end of file
+
+    /// Current values of all variables defined in `data.json`
+    pub fn lookup_all(&self) -> HashMap<String, i64> {
+        unsafe {
+            let mut n: usize = 0;
+            let ptr = gamedata_lookup_all(self.retro_data, &mut n);
+
+            let mut result = HashMap::with_capacity(n);
+            for i in 0..n {
+                let entry = &*ptr.add(i);
+                let name = CStr::from_ptr(entry.name).to_string_lossy().to_string();
+                result.insert(name, entry.value);
+            }
+
+            gamedata_free_variables(ptr, n);
+            result
+        }
+    }
+
+    /// Current value of a variable, `None` if it is not defined
+    pub fn lookup_value(&self, name: &str) -> Option<i64> {
+        let name = CString::new(name).ok()?;
+        let mut value = 0;
+        unsafe {
+            gamedata_lookup_value(self.retro_data, name.as_ptr(), &mut value).then_some(value)
+        }
+    }
+
+    /// Change of a variable during the last emulator frame
+    pub fn lookup_delta(&self, name: &str) -> Option<i64> {
+        let name = CString::new(name).ok()?;
+        let mut delta = 0;
+        unsafe {
+            gamedata_lookup_delta(self.retro_data, name.as_ptr(), &mut delta).then_some(delta)
+        }
+    }
+
+    /// Writes a variable into the emulator memory, `false` if it is not defined
+    pub fn set_value(&self, name: &str, value: i64) -> bool {
+        let Ok(name) = CString::new(name) else {
+            return false;
+        };
+        unsafe {
+            gamedata_set_value(self.retro_data, name.as_ptr(), value)
+        }
+    }
+
+    /// Copies the mapped console memory in address order, limited to the address
+    /// range `[start, end)` if given
+    pub fn read_ram(&self, range: Option<(usize, usize)>) -> Vec<u8> {
+        let (start, end) = range.unwrap_or((0, usize::MAX));
+        unsafe {
+            let mut n: usize = 0;
+            let ptr = gamedata_memory_blocks(self.retro_data, &mut n);
+
+            let mut ram = Vec::new();
+            for i in 0..n {
+                let block = &*ptr.add(i);
+                let block_start = block.offset.max(start);
+                let block_end = (block.offset + block.size).min(end);
+                if block_start < block_end {
+                    let bytes = std::slice::from_raw_parts(block.data, block.size);
+                    ram.extend_from_slice(&bytes[block_start - block.offset..block_end - block.offset]);
+                }
+            }
+
+            gamedata_free_memory_blocks(ptr);
+            ram
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        unsafe {
+            gamedata_frame(self.retro_data)
+        }
+    }
+
+    pub fn timestep(&self) -> u64 {
+        unsafe {
+            gamedata_timestep(self.retro_data)
+        }
+    }
+
+    /// Crop of the scenario file, `None` if the whole screen is used
+    pub fn get_crop(&self, player: u32) -> Option<Crop> {
+        let (mut x, mut y, mut width, mut height) = (0, 0, 0, 0);
+        unsafe {
+            gamedata_get_crop(self.retro_data, &mut x, &mut y, &mut width, &mut height, player);
+        }
+        let crop = Crop { x: x as u32, y: y as u32, width: width as u32, height: height as u32 };
+
+        (!crop.is_empty()).then_some(crop)
+    }
+
+    pub fn set_crop(&self, crop: Crop, player: u32) {
+        unsafe {
+            gamedata_set_crop(
+                self.retro_data,
+                crop.x as usize,
+                crop.y as usize,
+                crop.width as usize,
+                crop.height as usize,
+                player
+            );
+        }
+    }
+
+    /// Applies the `crops` of the scenario, players without one observe the whole screen
+    fn set_player_crops(&self, scenario: &Scenario) {
+        if scenario.crops.is_empty() {
+            return;
+        }
+        for player in 0..Self::max_players() {
+            let [x, y, width, height] = scenario.player_crop(player).unwrap_or_default();
+            self.set_crop(Crop { x, y, width, height }, player as u32);
+        }
+    }
+
+    /// Number of players the emulator has controllers for
+    pub fn max_players() -> usize {
+        unsafe { gamedata_max_players() }
+    }
+
+    pub fn progress(&self) -> ScenarioProgress {
+        let num_players = Self::max_players();
+        let mut progress = ScenarioProgress {
+            rewards: vec![0.0; num_players],
+            total_rewards: vec![0.0; num_players],
+            done: false,
+            frame: 0,
+        };
+        unsafe {
+            gamedata_get_progress(
+                self.retro_data,
+                progress.rewards.as_mut_ptr(),
+                progress.total_rewards.as_mut_ptr(),
+                &mut progress.done,
+                &mut progress.frame
+            );
+        }
+        progress
+    }
+
+    /// Restores the progress after the emulator state it was taken with
+    pub fn set_progress(&self, progress: &ScenarioProgress) {
+        unsafe {
+            gamedata_set_progress(
+                self.retro_data,
+                progress.rewards.as_ptr(),
+                progress.total_rewards.as_ptr(),
+                progress.done,
+                progress.frame
+            );
+        }
+    }
+}
+
+impl Drop for RustRetroGameData {
+    fn drop(&mut self) {
+        unsafe {
+            gamedata_free(self.retro_data);
+        }
+    }
+}
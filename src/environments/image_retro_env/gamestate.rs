@@ -1,7 +1,10 @@
 use std::fs::File;
 use std::io;
-use std::io::{Read, BufReader};
+use std::io::{Read, BufReader, BufWriter, Write};
+use std::path::Path;
+use flate2::Compression;
 use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 
 #[derive(Debug)]
 pub struct GameState {
@@ -19,4 +22,32 @@ impl GameState {
 
         Ok(GameState { buffer })
     }
-}
\ No newline at end of file
+
+    /// Writes the state gzip compressed, like the `.state` files of gym-retro
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+
+        let mut gz = GzEncoder::new(BufWriter::new(file), Compression::default());
+        gz.write_all(&self.buffer)?;
+        gz.finish()?.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_saved_state_loads_like_bundled_states() {
+        let bundled = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("games/Airstriker-Genesis/Level1.state");
+        let state = GameState::new(&bundled.to_string_lossy()).unwrap();
+
+        let path = std::env::temp_dir().join(format!("retro-rust-test-{}.state", std::process::id()));
+        state.save(&path).unwrap();
+        let reloaded = GameState::new(&path.to_string_lossy()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.buffer, state.buffer);
+    }
+}
@@ -0,0 +1,289 @@
+use image::{imageops::crop_imm, imageops::resize, imageops::FilterType, ImageBuffer, RgbImage};
+use serde::{Deserialize, Serialize};
+use crate::error::RetroError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    Rgb,
+    /// BT.601 luma: 0.299 R + 0.587 G + 0.114 B
+    Grayscale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    fn filter_type(&self) -> FilterType {
+        match self {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelLayout {
+    /// Pixels are stored row by row with interleaved channels
+    HWC,
+    /// One full plane per channel
+    CHW,
+}
+
+/// Screen region in pixels, a `width` or `height` of 0 extends to the screen border
+/// like the `crop` entry of gym-retro scenario files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Crop {
+    pub fn is_empty(&self) -> bool {
+        *self == Crop { x: 0, y: 0, width: 0, height: 0 }
+    }
+
+    /// Whether the region starts inside a screen of the given size
+    pub fn fits(&self, screen_width: u32, screen_height: u32) -> bool {
+        self.is_empty() || (self.x < screen_width && self.y < screen_height)
+    }
+
+    /// Region `(x, y, width, height)` clamped to a screen of the given size
+    pub fn resolve(&self, screen_width: u32, screen_height: u32) -> (u32, u32, u32, u32) {
+        let x = self.x.min(screen_width);
+        let y = self.y.min(screen_height);
+
+        let max_width = screen_width - x;
+        let max_height = screen_height - y;
+        let width = if self.width == 0 { max_width } else { self.width.min(max_width) };
+        let height = if self.height == 0 { max_height } else { self.height.min(max_height) };
+
+        (x, y, width, height)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObservationConfig {
+    /// Applied before resizing, `None` uses the crop of the scenario file
+    pub crop: Option<Crop>,
+    /// Output `(width, height)`, `None` keeps the native screen resolution
+    pub resize: Option<(u32, u32)>,
+    pub filter: ResizeFilter,
+    pub color: ColorMode,
+    pub layout: ChannelLayout,
+    /// Scales pixel values to `[0, 1]`, otherwise they stay in `[0, 255]`. Frames as `u8`
+    /// are available from `ImageRetroEnv::observation_u8`.
+    pub normalize: bool,
+    /// Number of consecutive observations stacked together, 1 disables stacking
+    pub stack_size: usize,
+    /// Takes the elementwise max of the last two emulator frames of a frame-skip step
+    pub max_pool: bool,
+}
+
+impl Default for ObservationConfig {
+    fn default() -> Self {
+        ObservationConfig {
+            crop: None,
+            resize: Some((84, 84)),
+            filter: ResizeFilter::Nearest,
+            color: ColorMode::Grayscale,
+            layout: ChannelLayout::HWC,
+            normalize: true,
+            stack_size: 4,
+            max_pool: true,
+        }
+    }
+}
+
+impl ObservationConfig {
+    pub fn channels(&self) -> usize {
+        match self.color {
+            ColorMode::Rgb => 3,
+            ColorMode::Grayscale => 1,
+        }
+    }
+
+    /// Output `(width, height)` for a screen of the given size
+    pub fn output_size(&self, screen_width: u32, screen_height: u32) -> (u32, u32) {
+        self.resize.unwrap_or_else(|| match self.crop {
+            Some(crop) => {
+                let (_, _, width, height) = crop.resolve(screen_width, screen_height);
+                (width, height)
+            }
+            None => (screen_width, screen_height),
+        })
+    }
+
+    /// Rejects a crop that starts at or past the border of a screen of the given size
+    pub fn check_crop(&self, screen_width: u32, screen_height: u32) -> Result<(), RetroError> {
+        match self.crop {
+            Some(crop) if !crop.fits(screen_width, screen_height) => Err(RetroError::InvalidConfig(format!(
+                "crop at ({}, {}) is outside the {screen_width}x{screen_height} screen",
+                crop.x, crop.y
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Number of values of a single preprocessed frame
+    pub fn frame_size(&self, screen_width: u32, screen_height: u32) -> usize {
+        let (w, h) = self.output_size(screen_width, screen_height);
+        w as usize * h as usize * self.channels()
+    }
+
+    pub fn process(&self, buffer: Vec<u8>, w: u32, h: u32) -> Vec<f32> {
+        let pixels = self.process_u8(buffer, w, h);
+
+        if self.normalize {
+            pixels.iter().map(|&p| p as f32 / 255.0).collect()
+        } else {
+            pixels.iter().map(|&p| p as f32).collect()
+        }
+    }
+
+    /// Runs every stage except normalization on an RGB888 screen buffer
+    pub fn process_u8(&self, buffer: Vec<u8>, w: u32, h: u32) -> Vec<u8> {
+        let img: RgbImage = ImageBuffer::from_raw(w, h, buffer)
+            .expect("Failed to convert screen buffer to image");
+
+        let img = match self.crop {
+            Some(crop) if !crop.is_empty() => {
+                let (x, y, width, height) = crop.resolve(w, h);
+                crop_imm(&img, x, y, width, height).to_image()
+            }
+            _ => img,
+        };
+
+        let (w, h) = img.dimensions();
+        let img = match self.resize {
+            Some((out_w, out_h)) if (out_w, out_h) != (w, h) => {
+                resize(&img, out_w, out_h, self.filter.filter_type())
+            }
+            _ => img,
+        };
+
+        match self.color {
+            ColorMode::Grayscale => img.pixels()
+                .map(|pixel| {
+                    (0.299 * pixel[0] as f32
+                        + 0.587 * pixel[1] as f32
+                        + 0.114 * pixel[2] as f32) as u8
+                })
+                .collect(),
+            ColorMode::Rgb => match self.layout {
+                ChannelLayout::HWC => img.into_raw(),
+                ChannelLayout::CHW => {
+                    let raw = img.into_raw();
+                    (0..3)
+                        .flat_map(|channel| raw.iter().skip(channel).step_by(3).copied())
+                        .collect()
+                }
+            },
+        }
+    }
+}
+
+/// Observes the console memory instead of the screen, e.g. the 128 bytes of Atari RAM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RamObservationConfig {
+    /// Address range `[start, end)`, `None` observes all mapped memory
+    pub range: Option<(usize, usize)>,
+    /// Unpacks every byte into 8 values of 0 or 1, least significant bit first
+    pub unpack_bits: bool,
+    /// Scales bytes to `[0, 1]`, unpacked bits already are
+    pub normalize: bool,
+    /// Number of consecutive observations stacked together, 1 disables stacking
+    pub stack_size: usize,
+}
+
+impl Default for RamObservationConfig {
+    fn default() -> Self {
+        RamObservationConfig {
+            range: None,
+            unpack_bits: false,
+            normalize: true,
+            stack_size: 1,
+        }
+    }
+}
+
+impl RamObservationConfig {
+    /// Number of values of a single observation of `ram_size` bytes
+    pub fn frame_size(&self, ram_size: usize) -> usize {
+        if self.unpack_bits { ram_size * 8 } else { ram_size }
+    }
+
+    pub fn process(&self, ram: &[u8]) -> Vec<f32> {
+        if self.unpack_bits {
+            ram.iter()
+                .flat_map(|&byte| (0..8).map(move |bit| ((byte >> bit) & 1) as f32))
+                .collect()
+        } else if self.normalize {
+            ram.iter().map(|&byte| byte as f32 / 255.0).collect()
+        } else {
+            ram.iter().map(|&byte| byte as f32).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_layouts() {
+        // 2x1 image: red pixel, green pixel
+        let buffer = vec![255, 0, 0, 0, 255, 0];
+
+        let mut config = ObservationConfig {
+            crop: None,
+            resize: None,
+            filter: ResizeFilter::Nearest,
+            color: ColorMode::Rgb,
+            layout: ChannelLayout::HWC,
+            normalize: false,
+            stack_size: 1,
+            max_pool: false,
+        };
+        assert_eq!(config.process_u8(buffer.clone(), 2, 1), vec![255, 0, 0, 0, 255, 0]);
+
+        config.layout = ChannelLayout::CHW;
+        assert_eq!(config.process_u8(buffer.clone(), 2, 1), vec![255, 0, 0, 255, 0, 0]);
+        assert_eq!(config.process(buffer.clone(), 2, 1), vec![255.0, 0.0, 0.0, 255.0, 0.0, 0.0]);
+
+        config.color = ColorMode::Grayscale;
+        config.normalize = true;
+        assert_eq!(config.frame_size(2, 1), 2);
+        assert_eq!(config.process_u8(buffer.clone(), 2, 1), vec![76, 149]);
+
+        config.crop = Some(Crop { x: 1, y: 0, width: 0, height: 0 });
+        assert!(config.check_crop(2, 1).is_ok());
+        assert_eq!(config.frame_size(2, 1), 1);
+        assert_eq!(config.process_u8(buffer, 2, 1), vec![149]);
+
+        config.crop = Some(Crop { x: 2, y: 0, width: 0, height: 0 });
+        assert!(matches!(config.check_crop(2, 1), Err(RetroError::InvalidConfig(_))));
+        config.crop = Some(Crop { x: 0, y: 1, width: 1, height: 1 });
+        assert!(matches!(config.check_crop(2, 1), Err(RetroError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_ram_unpack_bits() {
+        let config = RamObservationConfig { unpack_bits: true, ..RamObservationConfig::default() };
+        assert_eq!(config.frame_size(2), 16);
+
+        let observation = config.process(&[0b101, 0x80]);
+        assert_eq!(observation[..3], [1.0, 0.0, 1.0]);
+        assert_eq!(observation[8..], [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+    }
+}
@@ -1,23 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Platform {
     Atari,
     GB,
+    GBC,
     GBA,
     NES,
     SNES,
     Genesis,
+    Sms,
+    GameGear,
     PCE
 }
 
 impl Platform {
+    pub const ALL: [Platform; 10] = [
+        Platform::Atari,
+        Platform::GB,
+        Platform::GBC,
+        Platform::GBA,
+        Platform::NES,
+        Platform::SNES,
+        Platform::Genesis,
+        Platform::Sms,
+        Platform::GameGear,
+        Platform::PCE,
+    ];
+
+    /// Name of the system as used by gym-retro, e.g. the `-Atari2600` suffix of
+    /// `games/Pong-Atari2600` and the top-level key in `cores/info/*.json`
     pub fn as_str(&self) -> &'static str {
         match self {
-            Platform::Atari => "Atari",
-            Platform::GB => "GB",
-            Platform::GBA => "GBA",
-            Platform::NES => "NES",
-            Platform::SNES => "SNES",
+            Platform::Atari => "Atari2600",
+            Platform::GB => "GameBoy",
+            Platform::GBC => "GbColor",
+            Platform::GBA => "GbAdvance",
+            Platform::NES => "Nes",
+            Platform::SNES => "Snes",
             Platform::Genesis => "Genesis",
-            Platform::PCE => "PCE",
+            Platform::Sms => "Sms",
+            Platform::GameGear => "GameGear",
+            Platform::PCE => "PCEngine",
+        }
+    }
+
+    /// Resolves a gym-retro system name, e.g. the suffix of a game directory
+    pub fn from_name(name: &str) -> Option<Platform> {
+        Platform::ALL.into_iter().find(|platform| platform.as_str() == name)
+    }
+
+    /// Splits a game directory name like `SuperMarioBros-Nes` into game name and platform
+    pub fn split_game_dir(game_dir: &str) -> Option<(&str, Platform)> {
+        let (game_name, suffix) = game_dir.rsplit_once('-')?;
+        Platform::from_name(suffix).map(|platform| (game_name, platform))
+    }
+
+    pub fn game_dir(&self, game_name: &str) -> String {
+        format!("{game_name}-{}", self.as_str())
+    }
+
+    /// File in `cores/info` that describes the libretro core of this platform
+    pub fn core_info_file(&self) -> &'static str {
+        match self {
+            Platform::Atari => "atari.json",
+            Platform::GB | Platform::GBC => "gb.json",
+            Platform::GBA => "gba.json",
+            Platform::NES => "nes.json",
+            Platform::SNES => "snes.json",
+            Platform::Genesis | Platform::Sms | Platform::GameGear => "genesis.json",
+            Platform::PCE => "pce.json",
+        }
+    }
+
+    pub fn rom_extension(&self) -> &'static str {
+        match self {
+            Platform::Atari => "a26",
+            Platform::GB => "gb",
+            Platform::GBC => "gbc",
+            Platform::GBA => "gba",
+            Platform::NES => "nes",
+            Platform::SNES => "sfc",
+            Platform::Genesis => "md",
+            Platform::Sms => "sms",
+            Platform::GameGear => "gg",
+            Platform::PCE => "pce",
         }
     }
 
@@ -25,11 +92,42 @@ impl Platform {
         match self {
             Platform::Atari => "rom.a26",
             Platform::GB => "rom.gb",
+            Platform::GBC => "rom.gbc",
             Platform::GBA => "rom.gba",
             Platform::NES => "rom.nes",
             Platform::SNES => "rom.sfc",
             Platform::Genesis => "rom.md",
+            Platform::Sms => "rom.sms",
+            Platform::GameGear => "rom.gg",
             Platform::PCE => "rom.pce"
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_every_game_dir_resolves_to_a_platform() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+        for entry in fs::read_dir(root.join("games")).unwrap() {
+            let entry = entry.unwrap();
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            let (game_name, platform) = Platform::split_game_dir(&dir_name)
+                .unwrap_or_else(|| panic!("No platform for {dir_name}"));
+            assert_eq!(platform.game_dir(game_name), dir_name);
+
+            let core_info = fs::read_to_string(
+                root.join("cores").join("info").join(platform.core_info_file())
+            ).unwrap();
+            assert!(core_info.contains(&format!("\"{}\"", platform.as_str())));
+        }
+    }
+}
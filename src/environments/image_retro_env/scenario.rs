@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::error::RetroError;
+
+/// Whether a spec looks at the value of a variable or its change during the frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Measurement {
+    Absolute,
+    Delta,
+}
+
+/// Comparison of the measured value with the `reference` of a spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    Equal,
+    NegativeEqual,
+    NotEqual,
+    LessThan,
+    GreaterThan,
+    LessOrEqual,
+    GreaterOrEqual,
+    Nonzero,
+    Zero,
+    Negative,
+    Positive,
+    Sign,
+}
+
+/// Reward of a variable or of the elapsed time. Without `op` the measured value itself is
+/// multiplied with `reward` if positive and with `penalty` if negative.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardSpec {
+    #[serde(default = "default_reward_measurement", skip_serializing_if = "is_delta")]
+    pub measurement: Measurement,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<Operation>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub reference: i64,
+    #[serde(default, skip_serializing_if = "is_zero_f32")]
+    pub reward: f32,
+    #[serde(default, skip_serializing_if = "is_zero_f32")]
+    pub penalty: f32,
+}
+
+/// Condition on a variable that ends the episode, checked on the absolute value by default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DoneSpec {
+    #[serde(default = "default_done_measurement", skip_serializing_if = "is_absolute")]
+    pub measurement: Measurement,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<Operation>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub reference: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DoneCondition {
+    /// Done as soon as one variable or node is
+    #[default]
+    Any,
+    /// Done once all variables and nodes are
+    All,
+}
+
+/// Reward block of a scenario, one per player
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RewardConfig {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, RewardSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<RewardSpec>,
+    /// Lua function computing the reward, optionally prefixed with its scope like `lua:reward`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+}
+
+/// Done block of a scenario. Nodes nest further conditions, e.g. all of two variables
+/// inside an otherwise `any` block.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DoneNode {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, DoneSpec>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub nodes: BTreeMap<String, DoneNode>,
+    #[serde(default, skip_serializing_if = "is_any")]
+    pub condition: DoneCondition,
+    /// Lua function deciding the end of the episode, only read on the top level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+}
+
+/// Typed `scenario.json` of a game integration. Load, edit and save it, or apply it to a
+/// running environment with `ImageRetroEnv::set_scenario`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default, skip_serializing_if = "RewardConfig::is_empty")]
+    pub reward: RewardConfig,
+    /// Rewards per player, used instead of `reward` in multiplayer scenarios
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rewards: Vec<RewardConfig>,
+    #[serde(default, skip_serializing_if = "DoneNode::is_empty")]
+    pub done: DoneNode,
+    /// Button groups by name, one action is a combination of one entry per group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actions: Option<Vec<Vec<Vec<String>>>>,
+    /// Screen region `[x, y, width, height]` of the observation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<[u32; 4]>,
+    /// Screen regions per player, used instead of `crop` in multiplayer scenarios
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crops: Vec<[u32; 4]>,
+    /// Lua files relative to the integration directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<String>,
+}
+
+/// Scripts of a running scenario and the functions that drive it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptInfo {
+    /// Script files relative to the integration directory
+    pub scripts: Vec<String>,
+    /// Function computing the reward, `None` if the reward variables are used
+    pub reward_function: Option<String>,
+    /// Function ending the episode, `None` if the done variables are used
+    pub done_function: Option<String>,
+}
+
+fn default_reward_measurement() -> Measurement {
+    Measurement::Delta
+}
+
+fn default_done_measurement() -> Measurement {
+    Measurement::Absolute
+}
+
+fn is_delta(measurement: &Measurement) -> bool {
+    *measurement == Measurement::Delta
+}
+
+fn is_absolute(measurement: &Measurement) -> bool {
+    *measurement == Measurement::Absolute
+}
+
+fn is_any(condition: &DoneCondition) -> bool {
+    *condition == DoneCondition::Any
+}
+
+fn is_zero(value: &i64) -> bool {
+    *value == 0
+}
+
+fn is_zero_f32(value: &f32) -> bool {
+    *value == 0.0
+}
+
+impl RewardSpec {
+    /// `reward` per unit the variable increases during a frame
+    pub fn new(reward: f32) -> Self {
+        RewardSpec { reward, ..Default::default() }
+    }
+
+    pub fn penalty(mut self, penalty: f32) -> Self {
+        self.penalty = penalty;
+        self
+    }
+}
+
+impl Default for RewardSpec {
+    fn default() -> Self {
+        RewardSpec {
+            measurement: default_reward_measurement(),
+            op: None,
+            reference: 0,
+            reward: 0.0,
+            penalty: 0.0,
+        }
+    }
+}
+
+impl DoneSpec {
+    pub fn new(op: Operation, reference: i64) -> Self {
+        DoneSpec { measurement: default_done_measurement(), op: Some(op), reference }
+    }
+}
+
+impl RewardConfig {
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.time.is_none() && self.script.is_none()
+    }
+}
+
+impl DoneNode {
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.nodes.is_empty() && self.script.is_none() && is_any(&self.condition)
+    }
+
+    fn collect_variables<'a>(&'a self, names: &mut Vec<&'a str>) {
+        names.extend(self.variables.keys().map(String::as_str));
+        for node in self.nodes.values() {
+            node.collect_variables(names);
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self, RetroError> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .ok_or_else(|| RetroError::InvalidScenario(path.to_path_buf()))
+    }
+
+    /// Writes the scenario as pretty printed JSON
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json + "\n")
+    }
+
+    pub fn set_reward_variable(&mut self, name: &str, spec: RewardSpec) -> &mut Self {
+        self.reward.variables.insert(name.to_string(), spec);
+        self
+    }
+
+    pub fn set_reward_time(&mut self, spec: RewardSpec) -> &mut Self {
+        self.reward.time = Some(spec);
+        self
+    }
+
+    pub fn set_done_variable(&mut self, name: &str, spec: DoneSpec) -> &mut Self {
+        self.done.variables.insert(name.to_string(), spec);
+        self
+    }
+
+    pub fn set_done_node(&mut self, name: &str, node: DoneNode) -> &mut Self {
+        self.done.nodes.insert(name.to_string(), node);
+        self
+    }
+
+    pub fn set_done_condition(&mut self, condition: DoneCondition) -> &mut Self {
+        self.done.condition = condition;
+        self
+    }
+
+    /// Screen region of `player`, from `crops` if the scenario has them
+    pub fn player_crop(&self, player: usize) -> Option<[u32; 4]> {
+        if self.crops.is_empty() {
+            self.crop
+        } else {
+            self.crops.get(player).copied()
+        }
+    }
+
+    /// Names of all variables the rewards and done conditions refer to
+    pub fn referenced_variables(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = std::iter::once(&self.reward)
+            .chain(&self.rewards)
+            .flat_map(|reward| reward.variables.keys().map(String::as_str))
+            .collect();
+        self.done.collect_variables(&mut names);
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Checks that every referenced variable is one of `variables` and that the
+    /// scenario is consistent in itself
+    pub fn validate<'a>(&self, variables: impl IntoIterator<Item = &'a str>) -> Result<(), RetroError> {
+        let invalid = |message: &str| Err(RetroError::InvalidScenarioSpec(message.to_string()));
+
+        if !self.reward.is_empty() && !self.rewards.is_empty() {
+            return invalid("reward and rewards must not be used together");
+        }
+        if self.crop.is_some() && !self.crops.is_empty() {
+            return invalid("crop and crops must not be used together");
+        }
+        let uses_script = std::iter::once(&self.reward)
+            .chain(&self.rewards)
+            .any(|reward| reward.script.is_some())
+            || self.done.script.is_some();
+        if uses_script && self.scripts.is_empty() {
+            return invalid("reward or done script without a file in scripts");
+        }
+        if matches!(&self.actions, Some(actions) if actions.is_empty() || actions.iter().any(Vec::is_empty)) {
+            return invalid("actions must not contain empty button groups");
+        }
+
+        let variables: HashSet<&str> = variables.into_iter().collect();
+        match self.referenced_variables().into_iter().find(|name| !variables.contains(name)) {
+            Some(name) => Err(RetroError::UnknownVariable(name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Like `validate` with the variables defined in the `data.json` at `data_path`
+    pub fn validate_with_data(&self, data_path: &Path) -> Result<(), RetroError> {
+        let variables = Self::data_variables(data_path)?;
+        self.validate(variables.iter().map(String::as_str))
+    }
+
+    fn data_variables(data_path: &Path) -> Result<Vec<String>, RetroError> {
+        #[derive(Deserialize)]
+        struct DataFile {
+            #[serde(default)]
+            info: BTreeMap<String, serde_json::Value>,
+        }
+
+        let invalid = || RetroError::InvalidDataJson(PathBuf::from(data_path));
+        let json = fs::read_to_string(data_path).map_err(|_| invalid())?;
+        let data: DataFile = serde_json::from_str(&json).map_err(|_| invalid())?;
+        Ok(data.info.into_keys().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_edit_and_validate() {
+        let json = r#"{
+            "done": { "variables": { "lives": { "op": "equal", "reference": 0 } } },
+            "reward": { "variables": { "score": { "reward": 10.0 } } }
+        }"#;
+        let mut scenario: Scenario = serde_json::from_str(json).unwrap();
+        assert_eq!(scenario.reward.variables["score"], RewardSpec::new(10.0));
+        assert_eq!(scenario.done.variables["lives"], DoneSpec::new(Operation::Equal, 0));
+
+        scenario
+            .set_reward_variable("lives", RewardSpec::new(0.0).penalty(-1.0))
+            .set_done_node("stuck", DoneNode {
+                variables: BTreeMap::from([
+                    (String::from("x"), DoneSpec { measurement: Measurement::Delta, ..DoneSpec::new(Operation::Zero, 0) }),
+                    (String::from("timer"), DoneSpec::new(Operation::LessThan, 10)),
+                ]),
+                condition: DoneCondition::All,
+                ..Default::default()
+            });
+
+        let json = serde_json::to_string(&scenario).unwrap();
+        assert!(!json.contains("absolute") && !json.contains("\"any\""));
+        assert_eq!(serde_json::from_str::<Scenario>(&json).unwrap(), scenario);
+
+        assert!(scenario.validate(["lives", "score", "x", "timer"]).is_ok());
+        assert!(matches!(
+            scenario.validate(["lives", "score", "x"]),
+            Err(RetroError::UnknownVariable(name)) if name == "timer"
+        ));
+
+        scenario.reward.script = Some(String::from("reward"));
+        assert!(matches!(
+            scenario.validate(["lives", "score", "x", "timer"]),
+            Err(RetroError::InvalidScenarioSpec(_))
+        ));
+    }
+
+    #[test]
+    fn test_player_crops() {
+        let json = r#"{ "crops": [[0, 0, 160, 224], [160, 0, 160, 224]] }"#;
+        let mut scenario: Scenario = serde_json::from_str(json).unwrap();
+        assert_eq!(scenario.player_crop(1), Some([160, 0, 160, 224]));
+        assert_eq!(scenario.player_crop(2), None);
+        assert!(serde_json::to_string(&scenario).unwrap().contains("\"crops\""));
+        assert!(scenario.validate([]).is_ok());
+
+        scenario.crop = Some([0, 0, 320, 224]);
+        assert!(matches!(scenario.validate([]), Err(RetroError::InvalidScenarioSpec(_))));
+        scenario.crops.clear();
+        assert_eq!(scenario.player_crop(1), Some([0, 0, 320, 224]));
+    }
+
+    #[test]
+    fn test_bundled_scenarios_roundtrip() {
+        let games = Path::new(env!("CARGO_MANIFEST_DIR")).join("games");
+        for entry in fs::read_dir(games).unwrap() {
+            let path = entry.unwrap().path().join("scenario.json");
+            if !path.is_file() {
+                continue;
+            }
+            let scenario = Scenario::load(&path).unwrap();
+            scenario.validate_with_data(&path.with_file_name("data.json")).unwrap();
+
+            let json = serde_json::to_string(&scenario).unwrap();
+            assert_eq!(serde_json::from_str::<Scenario>(&json).unwrap(), scenario, "{}", path.display());
+        }
+    }
+}
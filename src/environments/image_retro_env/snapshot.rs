@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use crate::environments::image_retro_env::frame_stack::FrameStack;
+use crate::environments::image_retro_env::gamedata::ScenarioProgress;
+
+/// Complete in-memory state of an `ImageRetroEnv`, to branch off and rewind episodes
+/// in planners. Variables kept inside Lua scripts of the scenario, the frame counter the
+/// scripts see and the internal state of a `RewardFn` or `DoneFn` are not captured.
+#[derive(Clone)]
+pub struct EnvSnapshot {
+    pub(crate) emulator_state: Vec<u8>,
+    pub(crate) progress: ScenarioProgress,
+    pub(crate) frame_stack: FrameStack,
+    pub(crate) pool_screen: Option<Vec<u8>>,
+    pub(crate) skipped_frames: u8,
+    pub(crate) episode_steps: u64,
+    pub(crate) last_button_mask: Vec<u8>,
+    pub(crate) rng: StdRng,
+    pub(crate) previous_variables: HashMap<String, i64>,
+    pub(crate) step_variables: HashMap<String, i64>,
+    pub(crate) episode_rewards: Vec<f32>,
+}
+
+impl EnvSnapshot {
+    /// Size of the serialized emulator state in bytes
+    pub fn emulator_state_size(&self) -> usize {
+        self.emulator_state.len()
+    }
+
+    /// Agent steps taken in the episode when the snapshot was taken
+    pub fn episode_steps(&self) -> u64 {
+        self.episode_steps
+    }
+}
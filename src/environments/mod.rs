@@ -1,2 +1,6 @@
+pub mod env_builder;
 pub mod image_retro_env;
-pub mod movie_retro_env;
\ No newline at end of file
+pub mod movie_retro_env;
+pub mod ram_retro_env;
+#[cfg(unix)]
+pub mod vec_retro_env;
\ No newline at end of file
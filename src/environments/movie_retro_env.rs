@@ -1,3 +1,4 @@
+use std::path::{Path, PathBuf};
 use crate::environments::image_retro_env::ImageRetroEnv;
 use crate::environments::movie_retro_env::movie::RustRetroMovie;
 use crate::traits::retro_env::{RetroEnv, StepInfo};
@@ -5,51 +6,79 @@ use crate::traits::retro_env::{RetroEnv, StepInfo};
 pub mod movie;
 
 pub struct MovieRetroEnv {
-    image_env: ImageRetroEnv,
+    // Dropped before the emulator it records
     movie: RustRetroMovie,
+    image_env: ImageRetroEnv,
+    path: PathBuf,
 }
 
 impl MovieRetroEnv {
-    pub fn new(mut image_env: ImageRetroEnv) -> Self {
+    pub fn new(image_env: ImageRetroEnv) -> Self {
+        Self::with_path(image_env, Path::new("movie.bk2"))
+    }
+
+    /// Records to `path`, which is overwritten on every reset
+    pub fn with_path(image_env: ImageRetroEnv, path: &Path) -> Self {
         let movie = RustRetroMovie::new(
-            &mut image_env.emu,
-            String::from("movie.bk2"),
-            String::from(image_env.game_name.clone())
+            &image_env.emu,
+            path.to_string_lossy().to_string(),
+            image_env.game_name.clone(),
+            image_env.players()
         );
 
-        Self { image_env, movie }
+        Self { movie, image_env, path: path.to_path_buf() }
+    }
+
+    /// Starts a new recording from the save state, including the frame played by the reset
+    fn restart_movie(&mut self) {
+        // Finish the previous recording before its file is reopened, dropping it frees it
+        self.movie.close();
+        self.movie = RustRetroMovie::new(
+            &self.image_env.emu,
+            self.path.to_string_lossy().to_string(),
+            self.image_env.game_name.clone(),
+            self.image_env.players()
+        );
+
+        self.movie.step();
     }
 }
 
 impl RetroEnv for MovieRetroEnv {
     fn step(&mut self, action: usize) -> StepInfo {
         let button_bit_mask = self.image_env.get_button_bitmask(action);
+        let num_buttons = button_bit_mask.len() / self.image_env.players();
 
         let mut reward = 0.0;
         for _ in 0..self.image_env.frame_skip {
-            for (idx, value) in button_bit_mask.iter().enumerate() {
-                self.movie.set_key(idx, *value == 1);
+            let frame_button_mask = self.image_env.sticky_button_mask(&button_bit_mask);
+            for (idx, value) in frame_button_mask.iter().enumerate() {
+                self.movie.set_key(idx % num_buttons, *value == 1, idx / num_buttons);
             }
             self.movie.step();
-            reward += self.image_env.skipped_frame_step(button_bit_mask)
+            reward += self.image_env.skipped_frame_step(&frame_button_mask)
         }
 
         self.image_env.step_current_frame(reward)
     }
 
     fn reset(&mut self) -> StepInfo {
-        let step_info = self.image_env.reset();
-
-        self.movie.close();
-        self.movie = RustRetroMovie::new(
-            &mut self.image_env.emu,
-            String::from("movie.bk2"),
-            String::from(self.image_env.game_name.clone())
-        );
+        self.image_env.restart();
+        self.restart_movie();
 
-        self.movie.step();
+        // The no-op starts go through `step`, so they are part of the recording
+        for _ in 0..self.image_env.num_noops() {
+            if self.step(0).is_done {
+                self.image_env.restart();
+                self.restart_movie();
+                break;
+            }
+        }
+        self.image_env.first_step_info()
+    }
 
-        step_info
+    fn seed(&mut self, seed: u64) {
+        self.image_env.seed(seed);
     }
 
     fn num_actions(&self) -> usize {
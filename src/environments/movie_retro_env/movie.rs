@@ -1,5 +1,5 @@
 use std::ffi::CString;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_int};
 use crate::environments::image_retro_env::emulator::{RetroEmulator, RustRetroEmulator};
 
 #[repr(C)]
@@ -8,10 +8,11 @@ pub struct RetroMovie {
 }
 
 unsafe extern "C" {
-    fn movie_new(name: *const c_char) -> *mut RetroMovie;
+    fn movie_new(name: *const c_char, players: u32) -> *mut RetroMovie;
+    fn movie_free(movie: *mut RetroMovie);
     fn movie_close(movie: *mut RetroMovie);
     fn movie_step(movie: *mut RetroMovie) -> bool;
-    fn movie_set_key(movie: *mut RetroMovie, key: usize, set: bool);
+    fn movie_set_key(movie: *mut RetroMovie, key: c_int, set: bool, player: u32);
     fn movie_configure(movie: *mut RetroMovie, emulator: *mut RetroEmulator, name: *const c_char);
     fn movie_set_state(movie: *mut RetroMovie, data: *const u8, size: usize);
 }
@@ -23,25 +24,25 @@ pub struct RustRetroMovie {
 }
 
 impl RustRetroMovie {
-    pub fn new(emulator: *mut RustRetroEmulator, movie_name: String, game_name: String) -> Self {
+    pub fn new(emulator: &RustRetroEmulator, movie_name: String, game_name: String, players: usize) -> Self {
         unsafe {
             let movie_name = CString::new(movie_name).expect("CString::new failed");
-            let retro_movie = movie_new(movie_name.as_ptr());
+            let retro_movie = movie_new(movie_name.as_ptr(), players as u32);
 
             let game_name = CString::new(game_name).expect("CString::new failed");
-            movie_configure(retro_movie, (*emulator).retro_emulator, game_name.as_ptr());
-            movie_set_state(retro_movie, (*emulator).start_game_state.buffer.as_ptr(), (*emulator).start_game_state.buffer.len());
+            movie_configure(retro_movie, emulator.retro_emulator, game_name.as_ptr());
+            movie_set_state(retro_movie, emulator.start_game_state.buffer.as_ptr(), emulator.start_game_state.buffer.len());
 
             RustRetroMovie {
-                retro_emulator: (*emulator).retro_emulator,
+                retro_emulator: emulator.retro_emulator,
                 retro_movie
             }
         }
     }
 
-    pub fn set_key(&self, key: usize, set: bool) {
+    pub fn set_key(&self, key: usize, set: bool, player: usize) {
         unsafe {
-            movie_set_key(self.retro_movie, key, set);
+            movie_set_key(self.retro_movie, key as c_int, set, player as u32);
         }
     }
 
@@ -56,4 +57,12 @@ impl RustRetroMovie {
             movie_step(self.retro_movie);
         }
     }
-}
\ No newline at end of file
+}
+
+impl Drop for RustRetroMovie {
+    fn drop(&mut self) {
+        unsafe {
+            movie_free(self.retro_movie);
+        }
+    }
+}
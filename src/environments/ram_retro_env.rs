@@ -0,0 +1,69 @@
+use crate::environments::env_builder::EnvBuilder;
+use crate::environments::image_retro_env::ImageRetroEnv;
+use crate::environments::image_retro_env::observation::RamObservationConfig;
+use crate::environments::image_retro_env::platform::Platform;
+use crate::error::RetroError;
+use crate::traits::retro_env::{RetroEnv, StepInfo};
+
+/// Environment observing the console RAM instead of the screen. Frame-skip, rewards
+/// and resets are the ones of the underlying `ImageRetroEnv`.
+pub struct RamRetroEnv {
+    env: ImageRetroEnv,
+}
+
+impl RamRetroEnv {
+    pub fn new(
+        game_name: &str,
+        platform: Platform,
+        save_state_name: String,
+        ram_observation: RamObservationConfig
+    ) -> Result<Self, RetroError> {
+        EnvBuilder::new(game_name, platform, save_state_name)
+            .ram_observation(ram_observation)
+            .build()
+            .map(|env| RamRetroEnv { env })
+    }
+
+    /// Builds the environment of `builder`, observing the whole RAM if it has no RAM observation
+    pub fn from_builder(builder: &EnvBuilder) -> Result<Self, RetroError> {
+        let mut builder = builder.clone();
+        builder.ram_observation.get_or_insert_with(RamObservationConfig::default);
+        builder.build().map(|env| RamRetroEnv { env })
+    }
+
+    /// Number of values of a stacked observation
+    pub fn observation_size(&self) -> usize {
+        let (stack_size, channels, height, width) = self.env.observation_shape();
+        stack_size * channels * height * width
+    }
+
+    pub fn inner(&self) -> &ImageRetroEnv {
+        &self.env
+    }
+
+    pub fn inner_mut(&mut self) -> &mut ImageRetroEnv {
+        &mut self.env
+    }
+
+    pub fn into_inner(self) -> ImageRetroEnv {
+        self.env
+    }
+}
+
+impl RetroEnv for RamRetroEnv {
+    fn step(&mut self, action: usize) -> StepInfo {
+        self.env.step(action)
+    }
+
+    fn reset(&mut self) -> StepInfo {
+        self.env.reset()
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.env.seed(seed);
+    }
+
+    fn num_actions(&self) -> usize {
+        self.env.num_actions()
+    }
+}
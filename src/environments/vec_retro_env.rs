@@ -0,0 +1,433 @@
+pub mod protocol;
+pub mod shared_memory;
+pub mod worker;
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::io;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus, Stdio};
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::assets::AssetPaths;
+use crate::environments::env_builder::EnvBuilder;
+use crate::environments::vec_retro_env::protocol::{receive, send, Command, Reply, StepHeader};
+use crate::environments::vec_retro_env::shared_memory::SharedSlab;
+use crate::environments::vec_retro_env::worker::WORKER_SOCKET_ENV;
+
+const WORKER_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Default time a worker has to answer a command, building its environment included
+pub const WORKER_REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+static NEXT_SOCKET_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// How workers hand their observations back to the `VecRetroEnv`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Transport {
+    /// Observations are sent through the worker's socket
+    Socket,
+    /// Observations are written into a `SharedSlab`, the socket only signals completion
+    SharedMemory,
+}
+
+#[derive(Debug)]
+pub enum VecEnvError {
+    Spawn(io::Error),
+    /// The worker process exited, e.g. because its emulator crashed
+    WorkerCrashed { worker: usize, status: Option<ExitStatus> },
+    WorkerTimeout { worker: usize },
+    Worker { worker: usize, message: String },
+    Io { worker: usize, source: io::Error },
+}
+
+impl fmt::Display for VecEnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VecEnvError::Spawn(err) => write!(f, "Failed to spawn worker: {err}"),
+            VecEnvError::WorkerCrashed { worker, status: Some(status) } => {
+                write!(f, "Worker {worker} crashed with {status}")
+            }
+            VecEnvError::WorkerCrashed { worker, status: None } => {
+                write!(f, "Worker {worker} crashed")
+            }
+            VecEnvError::WorkerTimeout { worker } => {
+                write!(f, "Worker {worker} did not respond in time")
+            }
+            VecEnvError::Worker { worker, message } => write!(f, "Worker {worker}: {message}"),
+            VecEnvError::Io { worker, source } => write!(f, "Worker {worker}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for VecEnvError {}
+
+/// Batched results of all environments, observations are concatenated in worker order
+#[derive(Debug, Clone, Default)]
+pub struct VecStepInfo {
+    pub observations: Vec<f32>,
+    pub rewards: Vec<f32>,
+    pub is_done: Vec<bool>,
+    pub terminated: Vec<bool>,
+    pub truncated: Vec<bool>,
+    pub infos: Vec<HashMap<String, i64>>,
+    pub frames: Vec<u64>,
+    /// Last observation of episodes that ended in this step, the reset observation of
+    /// these environments is already in `observations`
+    pub final_observations: Vec<Option<Vec<f32>>>,
+}
+
+/// Zero-copy view of the latest step of a `VecRetroEnv` using `Transport::SharedMemory`
+pub struct SharedBatch<'a> {
+    pub slab: &'a SharedSlab,
+    pub infos: Vec<HashMap<String, i64>>,
+    pub frames: Vec<u64>,
+    /// Environments whose episode ended, their last observation is in `slab.final_observations()`
+    pub has_final_observation: Vec<bool>,
+}
+
+struct Worker {
+    child: Child,
+    stream: UnixStream,
+    socket_path: PathBuf,
+}
+
+/// Runs every environment in its own worker process, since libretro cores keep
+/// process-global state. Finished environments are reset automatically.
+///
+/// Workers re-launch the current executable, which has to call
+/// `worker::run_if_worker()` at the start of `main`. Use `with_worker_program`
+/// to launch another executable, e.g. the `retro_worker` binary of this crate.
+///
+/// After an error the workers are out of sync and the `VecRetroEnv` should be dropped.
+pub struct VecRetroEnv {
+    workers: Vec<Worker>,
+    slab: Option<SharedSlab>,
+    num_actions: usize,
+    observation_size: usize,
+}
+
+impl VecRetroEnv {
+    pub fn new(specs: Vec<EnvBuilder>) -> Result<Self, VecEnvError> {
+        let program = env::current_exe().map_err(VecEnvError::Spawn)?;
+        Self::with_worker_program(&program, specs)
+    }
+
+    pub fn with_worker_program(program: &Path, specs: Vec<EnvBuilder>) -> Result<Self, VecEnvError> {
+        Self::with_transport(program, specs, Transport::Socket)
+    }
+
+    pub fn with_transport(
+        program: &Path,
+        specs: Vec<EnvBuilder>,
+        transport: Transport
+    ) -> Result<Self, VecEnvError> {
+        Self::with_reply_timeout(program, specs, transport, WORKER_REPLY_TIMEOUT)
+    }
+
+    /// Like `with_transport`, a worker not answering within `reply_timeout` fails with
+    /// `VecEnvError::WorkerTimeout` instead of blocking forever
+    pub fn with_reply_timeout(
+        program: &Path,
+        specs: Vec<EnvBuilder>,
+        transport: Transport,
+        reply_timeout: Duration
+    ) -> Result<Self, VecEnvError> {
+        // Workers are pushed into the env right away, so dropping it on an error
+        // stops the ones already spawned and removes their sockets
+        let workers = Vec::with_capacity(specs.len());
+        let mut env = VecRetroEnv { workers, slab: None, num_actions: 0, observation_size: 0 };
+        for index in 0..specs.len() {
+            let worker = Self::spawn_worker(program, index, reply_timeout)?;
+            env.workers.push(worker);
+        }
+
+        for (index, mut spec) in specs.into_iter().enumerate() {
+            // Resolved here, so all workers find the same assets
+            spec.paths.get_or_insert_with(AssetPaths::from_env);
+            env.send(index, &Command::Init(Box::new(spec)))?;
+        }
+        for index in 0..env.workers.len() {
+            match env.receive(index)?.0 {
+                Reply::Ready { num_actions, observation_size } if index == 0 => {
+                    env.num_actions = num_actions;
+                    env.observation_size = observation_size;
+                }
+                Reply::Ready { num_actions, observation_size } => {
+                    if (num_actions, observation_size) != (env.num_actions, env.observation_size) {
+                        let message = format!(
+                            "Environment has {num_actions} actions and observations of size {observation_size}, \
+                             worker 0 has {} and {}",
+                            env.num_actions, env.observation_size
+                        );
+                        return Err(VecEnvError::Worker { worker: index, message });
+                    }
+                }
+                Reply::Error(message) => return Err(VecEnvError::Worker { worker: index, message }),
+                Reply::Step(_) => return Err(Self::unexpected_reply(index)),
+            }
+        }
+
+        if transport == Transport::SharedMemory {
+            env.attach_shared_memory()?;
+        }
+        Ok(env)
+    }
+
+    fn attach_shared_memory(&mut self) -> Result<(), VecEnvError> {
+        let name = format!(
+            "retro-rust-{}-{}.slab",
+            std::process::id(),
+            NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let slab = SharedSlab::create(&name, self.workers.len(), self.observation_size)
+            .map_err(VecEnvError::Spawn)?;
+
+        let num_envs = self.workers.len();
+        for index in 0..num_envs {
+            let path = slab.path().to_path_buf();
+            self.send(index, &Command::AttachSharedMemory { path, index, num_envs })?;
+        }
+        for index in 0..num_envs {
+            match self.receive(index)?.0 {
+                Reply::Ready { .. } => {}
+                Reply::Error(message) => return Err(VecEnvError::Worker { worker: index, message }),
+                Reply::Step(_) => return Err(Self::unexpected_reply(index)),
+            }
+        }
+
+        self.slab = Some(slab);
+        Ok(())
+    }
+
+    fn spawn_worker(program: &Path, index: usize, reply_timeout: Duration) -> Result<Worker, VecEnvError> {
+        let socket_path = env::temp_dir().join(format!(
+            "retro-rust-{}-{}.sock",
+            std::process::id(),
+            NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let listener = UnixListener::bind(&socket_path).map_err(VecEnvError::Spawn)?;
+        let child = listener.set_nonblocking(true).and_then(|_| {
+            std::process::Command::new(program)
+                .env(WORKER_SOCKET_ENV, &socket_path)
+                .stdin(Stdio::null())
+                .spawn()
+        });
+        let mut child = child.map_err(|err| {
+            let _ = std::fs::remove_file(&socket_path);
+            VecEnvError::Spawn(err)
+        })?;
+
+        let stream = Self::accept_worker(&listener, &mut child, index).and_then(|stream| {
+            stream.set_read_timeout(Some(reply_timeout)).map_err(VecEnvError::Spawn)?;
+            Ok(stream)
+        });
+        match stream {
+            Ok(stream) => Ok(Worker { child, stream, socket_path }),
+            Err(err) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = std::fs::remove_file(&socket_path);
+                Err(err)
+            }
+        }
+    }
+
+    fn accept_worker(listener: &UnixListener, child: &mut Child, index: usize) -> Result<UnixStream, VecEnvError> {
+        // Poll instead of blocking on accept, so a worker dying at startup is noticed
+        let start = Instant::now();
+        let stream = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    if let Ok(Some(status)) = child.try_wait() {
+                        return Err(VecEnvError::WorkerCrashed { worker: index, status: Some(status) });
+                    }
+                    if start.elapsed() > WORKER_CONNECT_TIMEOUT {
+                        return Err(VecEnvError::WorkerTimeout { worker: index });
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(err) => return Err(VecEnvError::Spawn(err)),
+            }
+        };
+        stream.set_nonblocking(false).map_err(VecEnvError::Spawn)?;
+        Ok(stream)
+    }
+
+    pub fn num_envs(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn num_actions(&self) -> usize {
+        self.num_actions
+    }
+
+    /// Number of values of a single environment's observation
+    pub fn observation_size(&self) -> usize {
+        self.observation_size
+    }
+
+    /// Steps every environment with its action, steps run in parallel across workers
+    pub fn step(&mut self, actions: &[usize]) -> Result<VecStepInfo, VecEnvError> {
+        assert_eq!(actions.len(), self.workers.len(), "Expected one action per environment");
+
+        for (index, action) in actions.iter().enumerate() {
+            self.send(index, &Command::Step(*action))?;
+        }
+        self.collect()
+    }
+
+    pub fn reset(&mut self) -> Result<VecStepInfo, VecEnvError> {
+        for index in 0..self.workers.len() {
+            self.send(index, &Command::Reset(None))?;
+        }
+        self.collect()
+    }
+
+    /// Resets environment `i` with seed `seed + i`
+    pub fn reset_with_seed(&mut self, seed: u64) -> Result<VecStepInfo, VecEnvError> {
+        for index in 0..self.workers.len() {
+            self.send(index, &Command::Reset(Some(seed.wrapping_add(index as u64))))?;
+        }
+        self.collect()
+    }
+
+    /// Like `step`, but leaves the observations in shared memory instead of copying them
+    ///
+    /// Panics if the environment was not created with `Transport::SharedMemory`.
+    pub fn step_in_place(&mut self, actions: &[usize]) -> Result<SharedBatch<'_>, VecEnvError> {
+        assert!(self.slab.is_some(), "step_in_place requires Transport::SharedMemory");
+        assert_eq!(actions.len(), self.workers.len(), "Expected one action per environment");
+
+        for (index, action) in actions.iter().enumerate() {
+            self.send(index, &Command::Step(*action))?;
+        }
+        let replies = self.collect_replies()?;
+
+        let mut batch = SharedBatch {
+            slab: self.slab.as_ref().unwrap(),
+            infos: Vec::with_capacity(replies.len()),
+            frames: Vec::with_capacity(replies.len()),
+            has_final_observation: Vec::with_capacity(replies.len()),
+        };
+        for (header, _) in replies {
+            batch.has_final_observation.push(header.has_final_observation);
+            batch.frames.push(header.frame);
+            batch.infos.push(header.info);
+        }
+        Ok(batch)
+    }
+
+    fn collect_replies(&mut self) -> Result<Vec<(StepHeader, Vec<f32>)>, VecEnvError> {
+        let mut replies = Vec::with_capacity(self.workers.len());
+
+        for index in 0..self.workers.len() {
+            let (reply, payload) = self.receive(index)?;
+            match reply {
+                Reply::Step(header) => replies.push((header, payload)),
+                Reply::Error(message) => return Err(VecEnvError::Worker { worker: index, message }),
+                Reply::Ready { .. } => return Err(Self::unexpected_reply(index)),
+            }
+        }
+        // Pairs with the release fence of the workers before they reply
+        fence(Ordering::Acquire);
+        Ok(replies)
+    }
+
+    fn collect(&mut self) -> Result<VecStepInfo, VecEnvError> {
+        let replies = self.collect_replies()?;
+        let size = self.observation_size;
+
+        let mut batch = VecStepInfo {
+            observations: Vec::with_capacity(replies.len() * size),
+            ..VecStepInfo::default()
+        };
+        for (index, (header, mut payload)) in replies.into_iter().enumerate() {
+            let final_observation = match &self.slab {
+                Some(slab) => {
+                    batch.observations.extend_from_slice(&slab.observations()[index * size..(index + 1) * size]);
+                    header.has_final_observation
+                        .then(|| slab.final_observations()[index * size..(index + 1) * size].to_vec())
+                }
+                None => {
+                    let final_observation = header.has_final_observation
+                        .then(|| payload.split_off(size));
+                    batch.observations.extend_from_slice(&payload);
+                    final_observation
+                }
+            };
+
+            batch.rewards.push(header.reward);
+            batch.is_done.push(header.terminated || header.truncated);
+            batch.terminated.push(header.terminated);
+            batch.truncated.push(header.truncated);
+            batch.infos.push(header.info);
+            batch.frames.push(header.frame);
+            batch.final_observations.push(final_observation);
+        }
+        Ok(batch)
+    }
+
+    fn send(&mut self, index: usize, command: &Command) -> Result<(), VecEnvError> {
+        let result = send(&mut self.workers[index].stream, command, &[]);
+        result.map_err(|err| self.worker_error(index, err))
+    }
+
+    fn receive(&mut self, index: usize) -> Result<(Reply, Vec<f32>), VecEnvError> {
+        let result = receive(&mut self.workers[index].stream);
+        result.map_err(|err| self.worker_error(index, err))
+    }
+
+    /// Distinguishes a crashed or stalled worker process from other communication failures
+    fn worker_error(&mut self, index: usize, err: io::Error) -> VecEnvError {
+        if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) {
+            return VecEnvError::WorkerTimeout { worker: index };
+        }
+        let child = &mut self.workers[index].child;
+        let exited = matches!(
+            err.kind(),
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset
+        );
+
+        if exited {
+            // Give the process a moment to be reaped before reading its status
+            let status = (0..50).find_map(|_| match child.try_wait() {
+                Ok(Some(status)) => Some(status),
+                _ => {
+                    thread::sleep(Duration::from_millis(10));
+                    None
+                }
+            });
+            return VecEnvError::WorkerCrashed { worker: index, status };
+        }
+        VecEnvError::Io { worker: index, source: err }
+    }
+
+    fn unexpected_reply(index: usize) -> VecEnvError {
+        VecEnvError::Worker { worker: index, message: String::from("Unexpected reply") }
+    }
+}
+
+impl Drop for VecRetroEnv {
+    fn drop(&mut self) {
+        for worker in self.workers.iter_mut() {
+            let _ = send(&mut worker.stream, &Command::Close, &[]);
+        }
+        for worker in self.workers.iter_mut() {
+            let start = Instant::now();
+            while !matches!(worker.child.try_wait(), Ok(Some(_))) {
+                if start.elapsed() > Duration::from_secs(1) {
+                    let _ = worker.child.kill();
+                    let _ = worker.child.wait();
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            let _ = std::fs::remove_file(&worker.socket_path);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::environments::env_builder::EnvBuilder;
+
+/// Messages sent from the `VecRetroEnv` to a worker process
+#[derive(Serialize, Deserialize)]
+pub enum Command {
+    Init(Box<EnvBuilder>),
+    Step(usize),
+    Reset(Option<u64>),
+    /// Switches the worker to write its results into a `SharedSlab` instead of replies
+    AttachSharedMemory { path: PathBuf, index: usize, num_envs: usize },
+    Close,
+}
+
+/// Messages sent from a worker process back to the `VecRetroEnv`
+#[derive(Serialize, Deserialize)]
+pub enum Reply {
+    Ready { num_actions: usize, observation_size: usize },
+    Step(StepHeader),
+    Error(String),
+}
+
+/// Everything of a `StepInfo` except the observations, which follow as payload
+/// or are written to shared memory
+#[derive(Serialize, Deserialize)]
+pub struct StepHeader {
+    pub reward: f32,
+    pub terminated: bool,
+    pub truncated: bool,
+    pub info: HashMap<String, i64>,
+    pub frame: u64,
+    pub timestep: u64,
+    /// The payload holds the last observation of the finished episode after the reset one
+    pub has_final_observation: bool,
+}
+
+/// Writes a message as `[json length][json][payload length][little endian f32 payload]`
+pub fn send<T: Serialize>(stream: &mut impl Write, message: &T, payload: &[f32]) -> io::Result<()> {
+    let json = serde_json::to_vec(message)?;
+
+    let mut buffer = Vec::with_capacity(8 + json.len() + payload.len() * 4);
+    buffer.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&json);
+    buffer.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    for value in payload {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    stream.write_all(&buffer)?;
+    stream.flush()
+}
+
+pub fn receive<T: DeserializeOwned>(stream: &mut impl Read) -> io::Result<(T, Vec<f32>)> {
+    let json_len = read_u32(stream)? as usize;
+    let mut json = vec![0u8; json_len];
+    stream.read_exact(&mut json)?;
+    let message = serde_json::from_slice(&json)?;
+
+    let payload_len = read_u32(stream)? as usize;
+    let mut bytes = vec![0u8; payload_len * 4];
+    stream.read_exact(&mut bytes)?;
+    let payload = bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+
+    Ok((message, payload))
+}
+
+fn read_u32(stream: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    stream.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_roundtrip() {
+        let header = StepHeader {
+            reward: 1.5,
+            terminated: true,
+            truncated: false,
+            info: HashMap::from([(String::from("lives"), 3)]),
+            frame: 40,
+            timestep: 10,
+            has_final_observation: false,
+        };
+        let mut buffer = Vec::new();
+        send(&mut buffer, &Reply::Step(header), &[0.25, -1.0]).unwrap();
+
+        let (reply, payload) = receive::<Reply>(&mut buffer.as_slice()).unwrap();
+        let Reply::Step(header) = reply else { panic!("Expected a step reply") };
+        assert_eq!(header.info["lives"], 3);
+        assert!(header.terminated);
+        assert_eq!(payload, vec![0.25, -1.0]);
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::slice;
+use memmap2::MmapMut;
+
+/// Memory mapped file shared by a `VecRetroEnv` and its workers. Every worker writes
+/// the results of its environment into its own slot, so the learner can read a whole
+/// batch without copying it out of the worker processes.
+///
+/// Layout, `N` environments with observations of size `S`:
+///
+/// | Region             | Type  | Shape    |
+/// |--------------------|-------|----------|
+/// | observations       | `f32` | `[N, S]` |
+/// | final observations | `f32` | `[N, S]` |
+/// | rewards            | `f32` | `[N]`    |
+/// | terminated         | `u8`  | `[N]`    |
+/// | truncated          | `u8`  | `[N]`    |
+pub struct SharedSlab {
+    mmap: MmapMut,
+    path: PathBuf,
+    owner: bool,
+    num_envs: usize,
+    observation_size: usize,
+}
+
+impl SharedSlab {
+    /// Creates the backing file, preferably in `/dev/shm` so it never touches the disk
+    pub fn create(name: &str, num_envs: usize, observation_size: usize) -> io::Result<Self> {
+        let shm_dir = Path::new("/dev/shm");
+        let dir = if shm_dir.is_dir() { shm_dir.to_path_buf() } else { std::env::temp_dir() };
+        let path = dir.join(name);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        file.set_len(Self::byte_size(num_envs, observation_size) as u64)?;
+
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        Ok(SharedSlab { mmap, path, owner: true, num_envs, observation_size })
+    }
+
+    /// Maps a slab created by another process
+    pub fn open(path: &Path, num_envs: usize, observation_size: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        if file.metadata()?.len() < Self::byte_size(num_envs, observation_size) as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Shared memory slab is too small"));
+        }
+
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        Ok(SharedSlab { mmap, path: path.to_path_buf(), owner: false, num_envs, observation_size })
+    }
+
+    fn byte_size(num_envs: usize, observation_size: usize) -> usize {
+        (2 * num_envs * observation_size + num_envs) * 4 + 2 * num_envs
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn num_envs(&self) -> usize {
+        self.num_envs
+    }
+
+    pub fn observation_size(&self) -> usize {
+        self.observation_size
+    }
+
+    fn f32_region(&self, offset: usize, len: usize) -> &[f32] {
+        // The mapping is page aligned and every f32 region starts at a multiple of 4 bytes
+        unsafe { slice::from_raw_parts((self.mmap.as_ptr() as *const f32).add(offset), len) }
+    }
+
+    fn f32_region_mut(&mut self, offset: usize, len: usize) -> &mut [f32] {
+        unsafe { slice::from_raw_parts_mut((self.mmap.as_mut_ptr() as *mut f32).add(offset), len) }
+    }
+
+    fn flags_offset(&self) -> usize {
+        (2 * self.num_envs * self.observation_size + self.num_envs) * 4
+    }
+
+    /// Observations of all environments as one contiguous `[N, S]` batch
+    pub fn observations(&self) -> &[f32] {
+        self.f32_region(0, self.num_envs * self.observation_size)
+    }
+
+    /// Last observations of episodes that ended in the latest step, `[N, S]`
+    pub fn final_observations(&self) -> &[f32] {
+        let size = self.num_envs * self.observation_size;
+        self.f32_region(size, size)
+    }
+
+    pub fn rewards(&self) -> &[f32] {
+        self.f32_region(2 * self.num_envs * self.observation_size, self.num_envs)
+    }
+
+    pub fn terminated(&self) -> &[u8] {
+        let offset = self.flags_offset();
+        &self.mmap[offset..offset + self.num_envs]
+    }
+
+    pub fn truncated(&self) -> &[u8] {
+        let offset = self.flags_offset() + self.num_envs;
+        &self.mmap[offset..offset + self.num_envs]
+    }
+
+    /// Writes the results of environment `index`, only called by the worker owning it
+    pub fn write(
+        &mut self,
+        index: usize,
+        observation: &[f32],
+        final_observation: Option<&[f32]>,
+        reward: f32,
+        terminated: bool,
+        truncated: bool
+    ) {
+        let size = self.observation_size;
+        let num_envs = self.num_envs;
+
+        self.f32_region_mut(index * size, size).copy_from_slice(observation);
+        if let Some(final_observation) = final_observation {
+            self.f32_region_mut((num_envs + index) * size, size).copy_from_slice(final_observation);
+        }
+        self.f32_region_mut(2 * num_envs * size + index, 1)[0] = reward;
+
+        let offset = self.flags_offset();
+        self.mmap[offset + index] = terminated as u8;
+        self.mmap[offset + num_envs + index] = truncated as u8;
+    }
+}
+
+impl Drop for SharedSlab {
+    fn drop(&mut self) {
+        if self.owner {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_writes_are_visible_to_owner() {
+        let name = format!("retro-rust-test-{}.slab", std::process::id());
+        let owner = SharedSlab::create(&name, 2, 3).unwrap();
+        let mut worker = SharedSlab::open(owner.path(), 2, 3).unwrap();
+
+        worker.write(1, &[1.0, 2.0, 3.0], Some(&[4.0, 5.0, 6.0]), 0.5, true, false);
+
+        assert_eq!(owner.observations(), &[0.0, 0.0, 0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(&owner.final_observations()[3..], &[4.0, 5.0, 6.0]);
+        assert_eq!(owner.rewards(), &[0.0, 0.5]);
+        assert_eq!(owner.terminated(), &[0, 1]);
+        assert_eq!(owner.truncated(), &[0, 0]);
+    }
+}
@@ -0,0 +1,116 @@
+use std::env;
+use std::io;
+use std::os::unix::net::UnixStream;
+use std::process;
+use std::sync::atomic::{fence, Ordering};
+use crate::environments::vec_retro_env::protocol::{receive, send, Command, Reply, StepHeader};
+use crate::environments::vec_retro_env::shared_memory::SharedSlab;
+use crate::traits::retro_env::{RetroEnv, StepInfo};
+
+/// Environment variable holding the socket a worker process connects to
+pub const WORKER_SOCKET_ENV: &str = "RETRO_RUST_WORKER_SOCKET";
+
+/// Turns the current process into an environment worker if it was spawned by a
+/// `VecRetroEnv`, otherwise returns immediately. Call this first thing in `main`.
+pub fn run_if_worker() {
+    let Ok(socket_path) = env::var(WORKER_SOCKET_ENV) else {
+        return;
+    };
+
+    let code = match run_worker(&socket_path) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("Retro worker failed: {err}");
+            1
+        }
+    };
+    process::exit(code);
+}
+
+pub fn run_worker(socket_path: &str) -> io::Result<()> {
+    let mut stream = UnixStream::connect(socket_path)?;
+
+    let (command, _) = receive::<Command>(&mut stream)?;
+    let Command::Init(spec) = command else {
+        let reply = Reply::Error(String::from("Expected Init as first command"));
+        return send(&mut stream, &reply, &[]);
+    };
+
+    let mut env = match spec.build() {
+        Ok(env) => env,
+        Err(err) => return send(&mut stream, &Reply::Error(err.to_string()), &[]),
+    };
+    let (stack_size, channels, height, width) = env.observation_shape();
+    let observation_size = stack_size * channels * height * width;
+    let ready = Reply::Ready { num_actions: env.num_actions(), observation_size };
+    send(&mut stream, &ready, &[])?;
+
+    let mut shared: Option<(SharedSlab, usize)> = None;
+    loop {
+        let (command, _) = receive::<Command>(&mut stream)?;
+        match command {
+            Command::Step(action) => {
+                let step_info = env.step(action);
+                if step_info.is_done {
+                    let reset_info = env.reset();
+                    let header = header(&step_info, true);
+                    reply(&mut stream, &mut shared, header, &reset_info.observation, Some(&step_info.observation))?;
+                } else {
+                    reply(&mut stream, &mut shared, header(&step_info, false), &step_info.observation, None)?;
+                }
+            }
+            Command::Reset(seed) => {
+                let step_info = match seed {
+                    Some(seed) => env.reset_with_seed(seed),
+                    None => env.reset(),
+                };
+                reply(&mut stream, &mut shared, header(&step_info, false), &step_info.observation, None)?;
+            }
+            Command::AttachSharedMemory { path, index, num_envs } => {
+                shared = Some((SharedSlab::open(&path, num_envs, observation_size)?, index));
+                send(&mut stream, &ready, &[])?;
+            }
+            Command::Init(_) => {
+                send(&mut stream, &Reply::Error(String::from("Worker is already initialized")), &[])?;
+            }
+            Command::Close => return Ok(()),
+        }
+    }
+}
+
+/// Sends the observations as payload, or writes them to the shared slab if attached
+fn reply(
+    stream: &mut UnixStream,
+    shared: &mut Option<(SharedSlab, usize)>,
+    header: StepHeader,
+    observation: &[f32],
+    final_observation: Option<&[f32]>
+) -> io::Result<()> {
+    match shared {
+        Some((slab, index)) => {
+            slab.write(*index, observation, final_observation, header.reward, header.terminated, header.truncated);
+            // Make the slab writes visible before the reply signals completion
+            fence(Ordering::Release);
+            send(stream, &Reply::Step(header), &[])
+        }
+        None => {
+            let mut payload = observation.to_vec();
+            if let Some(final_observation) = final_observation {
+                payload.extend_from_slice(final_observation);
+            }
+            send(stream, &Reply::Step(header), &payload)
+        }
+    }
+}
+
+fn header(step_info: &StepInfo, has_final_observation: bool) -> StepHeader {
+    StepHeader {
+        reward: step_info.reward,
+        terminated: step_info.terminated,
+        truncated: step_info.truncated,
+        info: step_info.info.clone(),
+        frame: step_info.frame,
+        timestep: step_info.timestep,
+        has_final_observation,
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use crate::environments::image_retro_env::platform::Platform;
+
+/// Errors of creating, restoring or accessing an environment
+#[derive(Debug)]
+pub enum RetroError {
+    /// None of the data directories contains an integration directory for the game
+    MissingGame(PathBuf),
+    /// The integration has no ROM, see the `import_roms` binary
+    MissingRom(PathBuf),
+    /// The ROM does not match any hash listed in `rom.sha`
+    RomHashMismatch { path: PathBuf, expected: Vec<String>, actual: String },
+    /// The core info JSON or the libretro core library of the platform is missing,
+    /// `path` is relative to the core directories if it was not found at all
+    MissingCore { platform: Platform, path: PathBuf },
+    /// The core refused to load the ROM
+    RomLoadFailed(PathBuf),
+    /// The save state could not be read or is not gzip compressed
+    BadStateFile { path: PathBuf, source: io::Error },
+    InvalidDataJson(PathBuf),
+    /// The scenario file is not part of the integration, nor an existing path
+    MissingScenario(PathBuf),
+    InvalidScenario(PathBuf),
+    /// A Lua script of the scenario failed to load, lacks a function or raised an error
+    ScriptError(String),
+    /// A `Scenario` built in code contradicts itself or was rejected by gym-retro
+    InvalidScenarioSpec(String),
+    /// The options of an `EnvBuilder` do not fit together
+    InvalidConfig(String),
+    /// The snapshot was taken from an environment with another game or observation shape
+    SnapshotMismatch,
+    /// The variable is not defined in `data.json`
+    UnknownVariable(String),
+}
+
+impl fmt::Display for RetroError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetroError::MissingGame(path) => write!(f, "Game {} not found", path.display()),
+            RetroError::MissingRom(path) => write!(f, "ROM not found at {}", path.display()),
+            RetroError::RomHashMismatch { path, expected, actual } => write!(
+                f,
+                "ROM {} has SHA1 {actual}, expected one of {}",
+                path.display(),
+                expected.join(", ")
+            ),
+            RetroError::MissingCore { platform, path } => {
+                write!(f, "Core {} for {} not found", path.display(), platform.as_str())
+            }
+            RetroError::RomLoadFailed(path) => write!(f, "Failed to load ROM {}", path.display()),
+            RetroError::BadStateFile { path, source } => {
+                write!(f, "Failed to load state {}: {source}", path.display())
+            }
+            RetroError::InvalidDataJson(path) => write!(f, "Invalid game data {}", path.display()),
+            RetroError::MissingScenario(path) => write!(f, "Scenario {} not found", path.display()),
+            RetroError::InvalidScenario(path) => write!(f, "Invalid scenario {}", path.display()),
+            RetroError::ScriptError(message) => write!(f, "Lua script error: {message}"),
+            RetroError::InvalidScenarioSpec(message) => write!(f, "Invalid scenario: {message}"),
+            RetroError::InvalidConfig(message) => write!(f, "Invalid environment config: {message}"),
+            RetroError::SnapshotMismatch => write!(f, "Snapshot does not belong to this environment"),
+            RetroError::UnknownVariable(name) => write!(f, "Unknown variable {name}"),
+        }
+    }
+}
+
+impl std::error::Error for RetroError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RetroError::BadStateFile { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -2,8 +2,12 @@ use crate::environments::image_retro_env::ImageRetroEnv;
 use crate::environments::image_retro_env::platform::Platform;
 use crate::traits::retro_env::RetroEnv;
 
+pub mod assets;
 pub mod environments;
+pub mod error;
+pub mod registry;
 pub mod traits;
+pub mod wrappers;
 
 pub fn main() {
     // A training scenario is defined by a (game, platform, save_state) triple
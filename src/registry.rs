@@ -0,0 +1,125 @@
+pub mod game_info;
+pub mod rom_import;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::assets::AssetPaths;
+use crate::environments::image_retro_env::platform::Platform;
+use crate::registry::game_info::GameInfo;
+
+pub struct GameRegistry {
+    games: Vec<GameInfo>,
+}
+
+impl GameRegistry {
+    /// Scans the data directories of `RETRO_RUST_DATA_PATH` and the ones shipped with this crate
+    pub fn new() -> io::Result<Self> {
+        Self::with_paths(&AssetPaths::from_env())
+    }
+
+    /// Scans all data directories of `paths`. An integration found in several of them
+    /// is located at the first one, extended by the files of the others.
+    pub fn with_paths(paths: &AssetPaths) -> io::Result<Self> {
+        let mut games: Vec<GameInfo> = Vec::new();
+        let mut indices: HashMap<String, usize> = HashMap::new();
+
+        for data_dir in paths.data_dirs.iter().filter(|dir| dir.is_dir()) {
+            for game in Self::scan(data_dir)?.games {
+                match indices.get(&game.id()) {
+                    Some(&index) => games[index].overlay(game),
+                    None => {
+                        indices.insert(game.id(), games.len());
+                        games.push(game);
+                    }
+                }
+            }
+        }
+        games.sort_by_key(|game| game.id());
+
+        Ok(GameRegistry { games })
+    }
+
+    /// Scans every integration directory below `games_path`
+    pub fn scan(games_path: &Path) -> io::Result<Self> {
+        let mut games = Vec::new();
+
+        for entry in fs::read_dir(games_path)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            if let Some(game) = GameInfo::from_dir(&path)? {
+                games.push(game);
+            }
+        }
+        games.sort_by_key(|game| game.id());
+
+        Ok(GameRegistry { games })
+    }
+
+    pub fn games(&self) -> &[GameInfo] {
+        &self.games
+    }
+
+    pub fn find(&self, game_name: &str, platform: Platform) -> Option<&GameInfo> {
+        self.games
+            .iter()
+            .find(|game| game.name == game_name && game.platform == platform)
+    }
+
+    pub fn by_platform(&self, platform: Platform) -> impl Iterator<Item = &GameInfo> {
+        self.games.iter().filter(move |game| game.platform == platform)
+    }
+
+    /// Games that have a ROM and at least one save state
+    pub fn playable(&self) -> impl Iterator<Item = &GameInfo> {
+        self.games.iter().filter(|game| game.is_playable())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::game_info::RomStatus;
+
+    #[test]
+    fn test_scan_bundled_games() {
+        let registry = GameRegistry::new().unwrap();
+        assert_eq!(registry.games().len(), 989);
+
+        let airstriker = registry.find("Airstriker", Platform::Genesis).unwrap();
+        assert!(airstriker.is_playable());
+        assert_eq!(airstriker.default_state_file().as_deref(), Some("Level1.state"));
+        assert!(registry.playable().any(|game| game.id() == "Airstriker-Genesis"));
+
+        let mario = registry.find("SuperMarioBros", Platform::NES).unwrap();
+        assert_eq!(mario.states.len(), 8);
+        assert_eq!(mario.default_state.as_deref(), Some("Level1-1"));
+        assert_eq!(mario.scenarios, vec![String::from("scenario")]);
+        assert!(matches!(mario.rom, RomStatus::ShaOnly(_)));
+
+        let sonic = registry.find("SonicTheHedgehog", Platform::Genesis).unwrap();
+        assert_eq!(sonic.scenarios, vec![String::from("contest"), String::from("scenario"), String::from("xpos")]);
+
+        assert!(registry.by_platform(Platform::Sms).all(|game| game.platform == Platform::Sms));
+        assert!(registry.games().iter().any(|game| game.uses_script));
+    }
+
+    #[test]
+    fn test_overlay_adds_states_to_bundled_games() {
+        let overlay = std::env::temp_dir().join(format!("retro-rust-overlay-{}", std::process::id()));
+        fs::create_dir_all(overlay.join("Airstriker-Genesis")).unwrap();
+        fs::write(overlay.join("Airstriker-Genesis").join("Custom.state"), []).unwrap();
+
+        let registry = GameRegistry::with_paths(&AssetPaths::bundled().with_data_dir(&overlay)).unwrap();
+        fs::remove_dir_all(&overlay).unwrap();
+
+        let airstriker = registry.find("Airstriker", Platform::Genesis).unwrap();
+        assert_eq!(airstriker.path, overlay.join("Airstriker-Genesis"));
+        assert_eq!(airstriker.states, vec![String::from("Custom"), String::from("Level1")]);
+        assert!(airstriker.is_playable());
+        assert_eq!(registry.games().len(), 989);
+    }
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::environments::image_retro_env::platform::Platform;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RomStatus {
+    /// The ROM file is placed inside the integration directory
+    Present(PathBuf),
+    /// Only the accepted SHA1 hashes of the ROM are known
+    ShaOnly(Vec<String>),
+    Missing,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameInfo {
+    pub name: String,
+    pub platform: Platform,
+    pub path: PathBuf,
+    /// Save state names without the `.state` extension
+    pub states: Vec<String>,
+    /// Scenario names without the `.json` extension, e.g. `scenario` and `xpos`
+    pub scenarios: Vec<String>,
+    pub default_state: Option<String>,
+    pub rom: RomStatus,
+    pub uses_script: bool,
+}
+
+#[derive(Deserialize, Default)]
+struct Metadata {
+    default_state: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct ScenarioScripts {
+    #[serde(default)]
+    scripts: Vec<String>,
+}
+
+impl GameInfo {
+    /// Reads an integration directory like `games/SuperMarioBros-Nes`
+    pub fn from_dir(path: &Path) -> io::Result<Option<Self>> {
+        let dir_name = path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let Some((name, platform)) = Platform::split_game_dir(&dir_name) else {
+            return Ok(None);
+        };
+
+        let states = Self::list_files(path, ".state")?;
+        let scenarios: Vec<String> = Self::list_files(path, ".json")?
+            .into_iter()
+            .filter(|name| name != "data" && name != "metadata")
+            .collect();
+
+        let metadata: Metadata = Self::read_json(&path.join("metadata.json"));
+        let scenario: ScenarioScripts = Self::read_json(&path.join("scenario.json"));
+
+        Ok(Some(GameInfo {
+            name: name.to_string(),
+            platform,
+            path: path.to_path_buf(),
+            states,
+            scenarios,
+            default_state: metadata.default_state,
+            rom: Self::rom_status(path, &platform),
+            uses_script: !scenario.scripts.is_empty(),
+        }))
+    }
+
+    /// Sorted names of the files ending with `suffix`, without it
+    fn list_files(path: &Path, suffix: &str) -> io::Result<Vec<String>> {
+        let mut names: Vec<String> = fs::read_dir(path)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                file_name.strip_suffix(suffix).map(String::from)
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn read_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> T {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn rom_status(path: &Path, platform: &Platform) -> RomStatus {
+        let rom_path = path.join(platform.rom_name());
+        if rom_path.is_file() {
+            return RomStatus::Present(rom_path);
+        }
+
+        match fs::read_to_string(path.join("rom.sha")) {
+            Ok(shas) => RomStatus::ShaOnly(
+                shas.lines()
+                    .map(|line| line.trim().to_lowercase())
+                    .filter(|line| !line.is_empty())
+                    .collect()
+            ),
+            Err(_) => RomStatus::Missing,
+        }
+    }
+
+    /// Adds the files of the same integration in a lower priority data directory
+    pub fn overlay(&mut self, other: GameInfo) {
+        for state in other.states {
+            if !self.states.contains(&state) {
+                self.states.push(state);
+            }
+        }
+        self.states.sort();
+        for scenario in other.scenarios {
+            if !self.scenarios.contains(&scenario) {
+                self.scenarios.push(scenario);
+            }
+        }
+        self.scenarios.sort();
+
+        if self.default_state.is_none() {
+            self.default_state = other.default_state;
+        }
+        let rom_rank = |rom: &RomStatus| match rom {
+            RomStatus::Present(_) => 2,
+            RomStatus::ShaOnly(_) => 1,
+            RomStatus::Missing => 0,
+        };
+        if rom_rank(&other.rom) > rom_rank(&self.rom) {
+            self.rom = other.rom;
+        }
+        if !self.path.join("scenario.json").is_file() {
+            self.uses_script = other.uses_script;
+        }
+    }
+
+    /// Directory name of the integration, e.g. `SuperMarioBros-Nes`
+    pub fn id(&self) -> String {
+        self.platform.game_dir(&self.name)
+    }
+
+    pub fn is_playable(&self) -> bool {
+        matches!(self.rom, RomStatus::Present(_)) && !self.states.is_empty()
+    }
+
+    /// File name of the default save state, as expected by `ImageRetroEnv::new`
+    pub fn default_state_file(&self) -> Option<String> {
+        self.default_state
+            .as_ref()
+            .filter(|state| self.states.contains(state))
+            .or(self.states.first())
+            .map(|state| format!("{state}.state"))
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use sha1::{Digest, Sha1};
+use zip::ZipArchive;
+use crate::registry::GameRegistry;
+use crate::registry::game_info::{GameInfo, RomStatus};
+
+/// Files larger than this are never ROMs of a supported platform
+const MAX_ROM_SIZE: u64 = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Copy,
+    /// Falls back to copying for ROMs that are read from a zip archive
+    Symlink,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Number of files that were hashed
+    pub scanned: usize,
+    /// Integration ids like `SuperMarioBros-Nes` that received a ROM
+    pub imported: Vec<String>,
+    /// Subset of `imported` that can be run right away
+    pub playable: Vec<String>,
+    /// Files and zip entries that could not be read or installed, the import went on without them
+    pub failed: Vec<(PathBuf, io::Error)>,
+}
+
+/// Hashes every file in `source` (a directory or zip archive) and installs matching
+/// ROMs into the integration directories of `registry` as `rom.[extension]`
+///
+/// Only fails if `source` itself can't be read, failures of single files are collected
+/// in `ImportReport::failed`.
+pub fn import_roms(
+    source: &Path,
+    registry: &GameRegistry,
+    mode: ImportMode
+) -> io::Result<ImportReport> {
+    let mut missing: HashMap<String, Vec<&GameInfo>> = HashMap::new();
+    for game in registry.games() {
+        if let RomStatus::ShaOnly(shas) = &game.rom {
+            for sha in shas {
+                missing.entry(sha.clone()).or_default().push(game);
+            }
+        }
+    }
+
+    let mut report = ImportReport::default();
+    if source.is_dir() {
+        import_dir(source, &mut missing, mode, &mut report)?;
+    } else if is_zip(source) {
+        import_zip(source, &mut missing, &mut report)?;
+    } else {
+        import_file(source, &mut missing, mode, &mut report)?;
+    }
+
+    report.imported.sort();
+    report.playable.sort();
+    Ok(report)
+}
+
+fn import_dir(
+    dir: &Path,
+    missing: &mut HashMap<String, Vec<&GameInfo>>,
+    mode: ImportMode,
+    report: &mut ImportReport
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                report.failed.push((dir.to_path_buf(), err));
+                continue;
+            }
+        };
+        let result = if path.is_dir() {
+            import_dir(&path, missing, mode, report)
+        } else if is_zip(&path) {
+            import_zip(&path, missing, report)
+        } else {
+            import_file(&path, missing, mode, report)
+        };
+        if let Err(err) = result {
+            report.failed.push((path, err));
+        }
+    }
+    Ok(())
+}
+
+fn import_file(
+    path: &Path,
+    missing: &mut HashMap<String, Vec<&GameInfo>>,
+    mode: ImportMode,
+    report: &mut ImportReport
+) -> io::Result<()> {
+    if fs::metadata(path)?.len() > MAX_ROM_SIZE {
+        return Ok(());
+    }
+    let rom = fs::read(path)?;
+    report.scanned += 1;
+
+    let Some(games) = missing.remove(&sha1_hex(&rom)) else {
+        return Ok(());
+    };
+    for game in games {
+        let rom_path = game.path.join(game.platform.rom_name());
+        match mode {
+            ImportMode::Copy => { fs::write(&rom_path, &rom)?; }
+            ImportMode::Symlink => symlink(&path.canonicalize()?, &rom_path)?,
+        }
+        record(game, report);
+    }
+    Ok(())
+}
+
+fn import_zip(
+    path: &Path,
+    missing: &mut HashMap<String, Vec<&GameInfo>>,
+    report: &mut ImportReport
+) -> io::Result<()> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(entry) => entry,
+            Err(err) => {
+                report.failed.push((path.join(format!("#{i}")), err.into()));
+                continue;
+            }
+        };
+        if entry.is_dir() || entry.size() > MAX_ROM_SIZE {
+            continue;
+        }
+        let entry_path = path.join(entry.name());
+        let mut rom = Vec::with_capacity(entry.size() as usize);
+        if let Err(err) = entry.read_to_end(&mut rom) {
+            report.failed.push((entry_path, err));
+            continue;
+        }
+        report.scanned += 1;
+
+        if let Some(games) = missing.remove(&sha1_hex(&rom)) {
+            for game in games {
+                match fs::write(game.path.join(game.platform.rom_name()), &rom) {
+                    Ok(()) => record(game, report),
+                    Err(err) => report.failed.push((entry_path.clone(), err)),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn record(game: &GameInfo, report: &mut ImportReport) {
+    report.imported.push(game.id());
+    if !game.states.is_empty() {
+        report.playable.push(game.id());
+    }
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+pub fn sha1_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(data))
+}
+
+#[cfg(unix)]
+fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    if link.symlink_metadata().is_ok() {
+        fs::remove_file(link)?;
+    }
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(not(unix))]
+fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    fs::copy(original, link).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+    use super::*;
+
+    const FAKE_ROM: &[u8] = b"not really a NES ROM";
+
+    /// Creates an integration `Fake-Nes` whose `rom.sha` matches `FAKE_ROM`
+    fn fake_registry(root: &Path) -> GameRegistry {
+        let game_dir = root.join("data").join("Fake-Nes");
+        fs::create_dir_all(&game_dir).unwrap();
+        fs::write(game_dir.join("rom.sha"), format!("{}\n", sha1_hex(FAKE_ROM))).unwrap();
+        fs::write(game_dir.join("Level1.state"), []).unwrap();
+        GameRegistry::scan(&root.join("data")).unwrap()
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("retro-rust-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("roms")).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_import_copy_skips_broken_files() {
+        let root = temp_root("import-copy");
+        let registry = fake_registry(&root);
+        fs::write(root.join("roms").join("fake.nes"), FAKE_ROM).unwrap();
+        fs::write(root.join("roms").join("broken.zip"), b"not a zip archive").unwrap();
+
+        let report = import_roms(&root.join("roms"), &registry, ImportMode::Copy).unwrap();
+        let rom_path = root.join("data").join("Fake-Nes").join("rom.nes");
+        let rom = fs::read(&rom_path).unwrap();
+        let is_symlink = rom_path.symlink_metadata().unwrap().is_symlink();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.imported, vec![String::from("Fake-Nes")]);
+        assert_eq!(report.playable, vec![String::from("Fake-Nes")]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, root.join("roms").join("broken.zip"));
+        assert_eq!(rom, FAKE_ROM);
+        assert!(!is_symlink);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_import_symlink_skips_unreadable_files() {
+        let root = temp_root("import-symlink");
+        let registry = fake_registry(&root);
+        fs::write(root.join("roms").join("fake.nes"), FAKE_ROM).unwrap();
+        std::os::unix::fs::symlink(root.join("missing.nes"), root.join("roms").join("dangling.nes")).unwrap();
+
+        let report = import_roms(&root.join("roms"), &registry, ImportMode::Symlink).unwrap();
+        let rom_path = root.join("data").join("Fake-Nes").join("rom.nes");
+        let target = fs::read_link(&rom_path).unwrap();
+        let source = root.join("roms").join("fake.nes").canonicalize().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.imported, vec![String::from("Fake-Nes")]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, root.join("roms").join("dangling.nes"));
+        assert_eq!(target, source);
+    }
+
+    #[test]
+    fn test_import_zip() {
+        let root = temp_root("import-zip");
+        let registry = fake_registry(&root);
+        let zip_path = root.join("roms").join("roms.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        writer.start_file("readme.txt", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"some other file").unwrap();
+        writer.start_file("fake.nes", SimpleFileOptions::default()).unwrap();
+        writer.write_all(FAKE_ROM).unwrap();
+        writer.finish().unwrap();
+
+        // Zip entries can't be linked to, so they are always copied
+        let report = import_roms(&zip_path, &registry, ImportMode::Symlink).unwrap();
+        let rom_path = root.join("data").join("Fake-Nes").join("rom.nes");
+        let rom = fs::read(&rom_path).unwrap();
+        let is_symlink = rom_path.symlink_metadata().unwrap().is_symlink();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.scanned, 2);
+        assert_eq!(report.imported, vec![String::from("Fake-Nes")]);
+        assert!(report.failed.is_empty());
+        assert_eq!(rom, FAKE_ROM);
+        assert!(!is_symlink);
+    }
+}
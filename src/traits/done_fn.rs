@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+/// How a `DoneFn` ends the episode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The game reached a terminal state, reported as `StepInfo::terminated`
+    Terminated,
+    /// The episode is cut off, reported as `StepInfo::truncated`
+    Truncated,
+}
+
+/// Everything a `DoneFn` sees after each agent step
+pub struct DoneContext<'a> {
+    /// Values of the variables of `data.json` after the step
+    pub current: &'a HashMap<String, i64>,
+    /// Values of the variables after the previous step, or the reset
+    pub previous: &'a HashMap<String, i64>,
+    /// Agent steps since the reset, including this one
+    pub episode_steps: u64,
+    /// Emulator frames since the reset
+    pub frame: u64,
+    /// Reward of every step of the episode, the last entry belongs to this step
+    pub rewards: &'a [f32],
+    /// Whether the `done` block of the scenario file is fulfilled
+    pub scenario_done: bool,
+}
+
+impl DoneContext<'_> {
+    /// Change of a variable during the step, 0 if it is not defined
+    pub fn delta(&self, name: &str) -> i64 {
+        let current = self.current.get(name).copied().unwrap_or_default();
+        let previous = self.previous.get(name).copied().unwrap_or_default();
+        current - previous
+    }
+}
+
+/// Ends episodes on conditions of its own, next to or instead of the scenario's.
+/// Implemented for closures:
+///
+/// ```ignore
+/// env.add_done_fn(|ctx: &DoneContext| (ctx.delta("lives") < 0).then_some(Termination::Terminated));
+/// ```
+pub trait DoneFn {
+    fn check(&mut self, context: &DoneContext) -> Option<Termination>;
+}
+
+impl<F: FnMut(&DoneContext) -> Option<Termination>> DoneFn for F {
+    fn check(&mut self, context: &DoneContext) -> Option<Termination> {
+        self(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closure_ends_episode() {
+        let previous = HashMap::from([(String::from("lives"), 3)]);
+        let current = HashMap::from([(String::from("lives"), 2)]);
+        let rewards = [0.0, 0.0, 0.0];
+        let context = DoneContext {
+            current: &current,
+            previous: &previous,
+            episode_steps: 3,
+            frame: 12,
+            rewards: &rewards,
+            scenario_done: false,
+        };
+
+        let mut life_lost = |ctx: &DoneContext| (ctx.delta("lives") < 0).then_some(Termination::Terminated);
+        assert_eq!(DoneFn::check(&mut life_lost, &context), Some(Termination::Terminated));
+
+        let mut stalled = |ctx: &DoneContext| {
+            (ctx.rewards.len() >= 3 && ctx.rewards.iter().rev().take(3).all(|r| *r == 0.0))
+                .then_some(Termination::Truncated)
+        };
+        assert_eq!(DoneFn::check(&mut stalled, &context), Some(Termination::Truncated));
+    }
+}
@@ -1 +1,3 @@
-pub mod retro_env;
\ No newline at end of file
+pub mod done_fn;
+pub mod retro_env;
+pub mod reward_fn;
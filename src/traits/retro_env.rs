@@ -1,11 +1,32 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
 pub struct StepInfo {
     pub observation: Vec<f32>,
     pub reward: f32,
-    pub is_done: bool
+    /// The episode is over, either terminated or truncated
+    pub is_done: bool,
+    /// The game reached a terminal state, e.g. game over
+    pub terminated: bool,
+    /// The episode was cut off before reaching a terminal state, e.g. by a time limit
+    pub truncated: bool,
+    /// Current values of the variables defined in the game's `data.json`
+    pub info: HashMap<String, i64>,
+    /// Emulator frames since the last reset
+    pub frame: u64,
+    pub timestep: u64,
 }
 
 pub trait RetroEnv {
     fn step(&mut self, action: usize) -> StepInfo;
+    /// Starts a new episode, the returned first observation has no reward and is not done
     fn reset(&mut self) -> StepInfo;
+    /// Seeds all randomness of the environment, so the same seed and actions reproduce
+    /// the same episode. Environments without randomness keep the default, which does nothing.
+    fn seed(&mut self, _seed: u64) {}
+    fn reset_with_seed(&mut self, seed: u64) -> StepInfo {
+        self.seed(seed);
+        self.reset()
+    }
     fn num_actions(&self) -> usize;
-}
\ No newline at end of file
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+/// Everything a `RewardFn` sees on each emulator frame
+pub struct RewardContext<'a> {
+    /// Values of the variables of `data.json` after the frame
+    pub current: &'a HashMap<String, i64>,
+    /// Values of the variables before the frame
+    pub previous: &'a HashMap<String, i64>,
+    /// Reward of the frame as defined by the scenario file or its Lua script
+    pub scenario_reward: f32,
+}
+
+impl RewardContext<'_> {
+    /// Change of a variable during the frame, 0 if it is not defined
+    pub fn delta(&self, name: &str) -> i64 {
+        let current = self.current.get(name).copied().unwrap_or_default();
+        let previous = self.previous.get(name).copied().unwrap_or_default();
+        current - previous
+    }
+}
+
+/// Replaces the scenario reward of every emulator frame, e.g. for reward shaping.
+/// Implemented for closures:
+///
+/// ```ignore
+/// env.set_reward_fn(|ctx: &RewardContext| ctx.scenario_reward + 0.01 * ctx.delta("x") as f32);
+/// ```
+pub trait RewardFn {
+    fn reward(&mut self, context: &RewardContext) -> f32;
+}
+
+impl<F: FnMut(&RewardContext) -> f32> RewardFn for F {
+    fn reward(&mut self, context: &RewardContext) -> f32 {
+        self(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closure_sees_deltas() {
+        let previous = HashMap::from([(String::from("x"), 10), (String::from("lives"), 3)]);
+        let current = HashMap::from([(String::from("x"), 14), (String::from("lives"), 2)]);
+        let context = RewardContext { current: &current, previous: &previous, scenario_reward: 1.0 };
+
+        let mut reward_fn = |ctx: &RewardContext| ctx.delta("x") as f32 + 100.0 * ctx.delta("lives") as f32;
+        assert_eq!(RewardFn::reward(&mut reward_fn, &context), -96.0);
+        assert_eq!(context.delta("missing"), 0);
+    }
+}
@@ -0,0 +1 @@
+pub mod time_limit;
\ No newline at end of file
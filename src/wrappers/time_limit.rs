@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use crate::traits::retro_env::{RetroEnv, StepInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EpisodeLimit {
+    /// Maximum number of agent steps per episode
+    Steps(u64),
+    /// Maximum number of emulator frames per episode
+    Frames(u64),
+}
+
+impl EpisodeLimit {
+    pub fn is_reached(&self, steps: u64, frames: u64) -> bool {
+        match self {
+            EpisodeLimit::Steps(max_steps) => steps >= *max_steps,
+            EpisodeLimit::Frames(max_frames) => frames >= *max_frames,
+        }
+    }
+
+    /// Marks the step as truncated if the limit is reached before the game terminated
+    pub fn apply(&self, step_info: &mut StepInfo, steps: u64) {
+        if !step_info.terminated && self.is_reached(steps, step_info.frame) {
+            step_info.truncated = true;
+            step_info.is_done = true;
+        }
+    }
+}
+
+/// Truncates the episodes of any `RetroEnv` after a fixed number of steps or frames
+pub struct TimeLimit<E: RetroEnv> {
+    env: E,
+    limit: EpisodeLimit,
+    steps: u64,
+}
+
+impl<E: RetroEnv> TimeLimit<E> {
+    pub fn new(env: E, limit: EpisodeLimit) -> Self {
+        Self { env, limit, steps: 0 }
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.env
+    }
+
+    pub fn inner_mut(&mut self) -> &mut E {
+        &mut self.env
+    }
+
+    pub fn into_inner(self) -> E {
+        self.env
+    }
+}
+
+impl<E: RetroEnv> RetroEnv for TimeLimit<E> {
+    fn step(&mut self, action: usize) -> StepInfo {
+        let mut step_info = self.env.step(action);
+        self.steps += 1;
+        self.limit.apply(&mut step_info, self.steps);
+
+        step_info
+    }
+
+    fn reset(&mut self) -> StepInfo {
+        self.steps = 0;
+        self.env.reset()
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.env.seed(seed);
+    }
+
+    fn num_actions(&self) -> usize {
+        self.env.num_actions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct CountingEnv {
+        frames: u64,
+    }
+
+    impl CountingEnv {
+        fn step_info(&self) -> StepInfo {
+            StepInfo {
+                observation: vec![],
+                reward: 0.0,
+                is_done: self.frames >= 100,
+                terminated: self.frames >= 100,
+                truncated: false,
+                info: HashMap::new(),
+                frame: self.frames,
+                timestep: self.frames / 4,
+            }
+        }
+    }
+
+    impl RetroEnv for CountingEnv {
+        fn step(&mut self, _action: usize) -> StepInfo {
+            self.frames += 4;
+            self.step_info()
+        }
+
+        fn reset(&mut self) -> StepInfo {
+            self.frames = 0;
+            self.step_info()
+        }
+
+        fn num_actions(&self) -> usize { 1 }
+    }
+
+    #[test]
+    fn test_time_limit_truncates() {
+        let mut env = TimeLimit::new(CountingEnv { frames: 0 }, EpisodeLimit::Steps(3));
+        env.reset();
+        assert!(!env.step(0).is_done);
+        assert!(!env.step(0).is_done);
+        let step_info = env.step(0);
+        assert!(step_info.truncated && step_info.is_done && !step_info.terminated);
+
+        env.reset();
+        assert!(!env.step(0).truncated);
+
+        let mut env = TimeLimit::new(CountingEnv { frames: 0 }, EpisodeLimit::Frames(1000));
+        env.reset();
+        let step_info = (0..25).map(|_| env.step(0)).last().unwrap();
+        assert!(step_info.terminated && !step_info.truncated);
+    }
+}
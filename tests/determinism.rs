@@ -0,0 +1,35 @@
+use retro_rust::environments::image_retro_env::ImageRetroEnv;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::traits::retro_env::RetroEnv;
+
+fn rollout(env: &mut ImageRetroEnv, seed: u64) -> (Vec<Vec<u32>>, Vec<u32>) {
+    let mut observations = Vec::new();
+    let mut rewards = Vec::new();
+
+    let step_info = env.reset_with_seed(seed);
+    observations.push(step_info.observation.iter().map(|v| v.to_bits()).collect());
+
+    for i in 0..300 {
+        let step_info = env.step((i * 7) % env.num_actions());
+        observations.push(step_info.observation.iter().map(|v| v.to_bits()).collect());
+        rewards.push(step_info.reward.to_bits());
+        if step_info.is_done {
+            break;
+        }
+    }
+    (observations, rewards)
+}
+
+#[test]
+fn test_same_seed_reproduces_episode() {
+    let mut env = ImageRetroEnv::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+    env.noop_max = 30;
+    env.sticky_action_prob = 0.25;
+
+    let first = rollout(&mut env, 42);
+    let _ = rollout(&mut env, 7);
+    let second = rollout(&mut env, 42);
+
+    assert_eq!(first.0, second.0);
+    assert_eq!(first.1, second.1);
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use retro_rust::environments::env_builder::EnvBuilder;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::environments::image_retro_env::scenario::Scenario;
+use retro_rust::error::RetroError;
+use retro_rust::traits::retro_env::RetroEnv;
+
+#[test]
+fn test_user_script_errors_surface() {
+    let script = std::env::temp_dir().join(format!("retro-rust-script-{}.lua", std::process::id()));
+    fs::write(
+        &script,
+        "calls = 0\n\
+         function never_done() return false end\n\
+         function flaky()\n  calls = calls + 1\n  if calls > 20 then error(\"boom\") end\n  return 1\nend\n"
+    ).unwrap();
+
+    let mut env = EnvBuilder::new("Airstriker", Platform::Genesis, String::from("Level1.state"))
+        .lua_script(&script)
+        .reward_function("flaky")
+        .done_function("never_done")
+        .build()
+        .unwrap();
+
+    let scripts = env.scripts();
+    assert_eq!(scripts.reward_function.as_deref(), Some("flaky"));
+    assert_eq!(scripts.done_function.as_deref(), Some("never_done"));
+    assert!(scripts.scripts.iter().any(|path| path.ends_with(".lua")));
+
+    env.reset();
+    let mut step_info = env.step(0);
+    assert!(step_info.reward > 0.0);
+    assert!(!step_info.truncated);
+    for _ in 0..100 {
+        if step_info.truncated {
+            break;
+        }
+        step_info = env.step(0);
+    }
+    assert!(step_info.truncated);
+    assert!(matches!(env.script_error(), Some(RetroError::ScriptError(message)) if message.contains("boom")));
+
+    // Frames after the error neither repeat the last reward nor end the episode
+    let step_info = env.step(0);
+    assert_eq!(step_info.reward, 0.0);
+    assert!(step_info.truncated && !step_info.terminated);
+
+    // The builder's script and functions outlive a scenario change and the next reset
+    let scenario = Scenario::load(&env.game_path.join("scenario.json")).unwrap();
+    env.set_scenario(&scenario).unwrap();
+    env.reset();
+    fs::remove_file(&script).unwrap();
+
+    let scripts = env.scripts();
+    assert_eq!(scripts.reward_function.as_deref(), Some("flaky"));
+    assert_eq!(scripts.done_function.as_deref(), Some("never_done"));
+    assert!(env.script_error().is_none());
+}
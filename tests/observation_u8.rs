@@ -0,0 +1,25 @@
+use retro_rust::environments::env_builder::EnvBuilder;
+use retro_rust::environments::image_retro_env::observation::ObservationConfig;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::traits::retro_env::RetroEnv;
+
+#[test]
+fn test_u8_observation_matches_unnormalized_frame() {
+    let observation = ObservationConfig {
+        resize: Some((64, 64)),
+        normalize: false,
+        stack_size: 1,
+        max_pool: false,
+        ..ObservationConfig::default()
+    };
+    let mut env = EnvBuilder::new("Airstriker", Platform::Genesis, String::from("Level1.state"))
+        .observation(observation)
+        .build()
+        .unwrap();
+    env.reset();
+    let step_info = env.step(0);
+
+    let frame = env.observation_u8();
+    assert_eq!(frame.len(), 64 * 64);
+    assert_eq!(step_info.observation, frame.iter().map(|&pixel| pixel as f32).collect::<Vec<f32>>());
+}
@@ -0,0 +1,36 @@
+use std::fs;
+use retro_rust::environments::image_retro_env::ImageRetroEnv;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::traits::retro_env::RetroEnv;
+
+/// Resident set size of this process in bytes
+fn resident_memory() -> usize {
+    let statm = fs::read_to_string("/proc/self/statm").unwrap();
+    let pages: usize = statm.split_whitespace().nth(1).unwrap().parse().unwrap();
+    pages * 4096
+}
+
+fn create_and_drop_env() {
+    let mut env = ImageRetroEnv::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+    env.reset();
+    for _ in 0..10 {
+        env.step(0);
+    }
+}
+
+#[test]
+fn test_dropping_envs_frees_memory() {
+    // Warm up allocator pools and one-time core initialization
+    for _ in 0..5 {
+        create_and_drop_env();
+    }
+    let before = resident_memory();
+
+    for _ in 0..50 {
+        create_and_drop_env();
+    }
+    let after = resident_memory();
+
+    let growth = after.saturating_sub(before);
+    assert!(growth < 32 * 1024 * 1024, "Memory grew by {growth} bytes over 50 environments");
+}
@@ -0,0 +1,30 @@
+use retro_rust::environments::image_retro_env::ImageRetroEnv;
+use retro_rust::environments::image_retro_env::gamestate::GameState;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::traits::retro_env::RetroEnv;
+
+#[test]
+fn test_saved_state_loads_back() {
+    let mut env = ImageRetroEnv::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+    env.reset_with_seed(0);
+    for i in 0..50 {
+        env.step(i % env.num_actions());
+    }
+
+    let dir = std::env::temp_dir().join(format!("retro-rust-save-state-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = env.save_state(dir.join("Boss.v2")).unwrap();
+    assert_eq!(path, dir.join("Boss.v2.state"));
+    assert_eq!(env.save_state(dir.join("Boss.state")).unwrap(), dir.join("Boss.state"));
+
+    let saved = env.emu.get_state().unwrap();
+    for _ in 0..50 {
+        env.step(0);
+    }
+    let state = GameState::new(&path.to_string_lossy()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(state.buffer, saved);
+    assert!(env.emu.set_state(&state.buffer));
+    assert_eq!(env.emu.get_state().unwrap(), saved);
+}
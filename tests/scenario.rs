@@ -0,0 +1,31 @@
+use retro_rust::environments::image_retro_env::ImageRetroEnv;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::environments::image_retro_env::scenario::{DoneSpec, Operation, RewardSpec, Scenario};
+use retro_rust::error::RetroError;
+use retro_rust::traits::retro_env::RetroEnv;
+
+#[test]
+fn test_apply_edited_scenario() {
+    let mut env = ImageRetroEnv::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+    let num_actions = env.num_actions();
+
+    let mut scenario = Scenario::load(&env.game_path.join("scenario.json")).unwrap();
+    scenario.reward.variables.clear();
+    scenario.done.variables.clear();
+    scenario.set_done_variable("lives", DoneSpec::new(Operation::Equal, 9));
+    env.set_scenario(&scenario).unwrap();
+    assert_eq!(env.num_actions(), num_actions);
+
+    env.reset();
+    env.set_var("lives", 9).unwrap();
+    let step_info = env.step(0);
+    assert_eq!(step_info.reward, 0.0);
+    assert!(step_info.terminated);
+
+    scenario.crop = Some([10_000, 0, 0, 0]);
+    assert!(matches!(env.set_scenario(&scenario), Err(RetroError::InvalidConfig(_))));
+    scenario.crop = None;
+
+    scenario.set_reward_variable("no_such_variable", RewardSpec::new(1.0));
+    assert!(matches!(env.set_scenario(&scenario), Err(RetroError::UnknownVariable(_))));
+}
@@ -0,0 +1,30 @@
+use retro_rust::environments::image_retro_env::ImageRetroEnv;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::traits::retro_env::{RetroEnv, StepInfo};
+
+fn play(env: &mut ImageRetroEnv, steps: usize) -> Vec<StepInfo> {
+    (0..steps).map(|i| env.step((i * 5) % env.num_actions())).collect()
+}
+
+#[test]
+fn test_restore_replays_branch_exactly() {
+    let mut env = ImageRetroEnv::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+    env.sticky_action_prob = 0.25;
+    env.reset_with_seed(3);
+    play(&mut env, 50);
+
+    let snapshot = env.snapshot();
+    let reward_at_snapshot = env.episode_reward();
+    let first = play(&mut env, 100);
+
+    env.restore(&snapshot).unwrap();
+    assert_eq!(env.episode_reward(), reward_at_snapshot);
+    let second = play(&mut env, 100);
+
+    for (a, b) in first.iter().zip(&second) {
+        assert_eq!(a.observation, b.observation);
+        assert_eq!(a.reward, b.reward);
+        assert_eq!(a.frame, b.frame);
+        assert_eq!(a.info, b.info);
+    }
+}
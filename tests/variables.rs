@@ -0,0 +1,29 @@
+use retro_rust::environments::image_retro_env::ImageRetroEnv;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::error::RetroError;
+use retro_rust::traits::done_fn::{DoneContext, Termination};
+use retro_rust::traits::retro_env::RetroEnv;
+
+#[test]
+fn test_read_and_write_variables() {
+    let mut env = ImageRetroEnv::new("Airstriker", Platform::Genesis, String::from("Level1.state"));
+    env.reset();
+
+    let variables = env.variables();
+    assert_eq!(env.get_var("lives"), variables.get("lives").copied());
+    assert!(env.get_var("no_such_variable").is_none());
+
+    // Neither is the write of the lives a change seen by done conditions
+    env.add_done_fn(|ctx: &DoneContext| (ctx.delta("lives") != 0).then_some(Termination::Truncated));
+    env.set_var("lives", 9).unwrap();
+    assert_eq!(env.get_var("lives"), Some(9));
+    assert!(matches!(env.set_var("no_such_variable", 1), Err(RetroError::UnknownVariable(_))));
+
+    // Writing the score is not rewarded as progress
+    let score = env.get_var("score").unwrap();
+    env.set_var("score", score + 1000).unwrap();
+    let step_info = env.step(0);
+    assert!(step_info.reward < 1000.0);
+    assert_eq!(step_info.info["lives"], 9);
+    assert!(!step_info.truncated);
+}
@@ -0,0 +1,48 @@
+#![cfg(unix)]
+
+use std::path::Path;
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::environments::env_builder::EnvBuilder;
+use retro_rust::environments::vec_retro_env::{Transport, VecRetroEnv};
+
+fn specs() -> Vec<EnvBuilder> {
+    (0..2)
+        .map(|_| EnvBuilder::new("Airstriker", Platform::Genesis, String::from("Level1.state")))
+        .collect()
+}
+
+#[test]
+fn test_parallel_rollout() {
+    let mut env = VecRetroEnv::with_worker_program(
+        Path::new(env!("CARGO_BIN_EXE_retro_worker")),
+        specs()
+    ).unwrap();
+
+    let step_info = env.reset_with_seed(0).unwrap();
+    assert_eq!(step_info.observations.len(), 2 * env.observation_size());
+
+    for _ in 0..100 {
+        let step_info = env.step(&[0, env.num_actions() - 1]).unwrap();
+        assert_eq!(step_info.observations.len(), 2 * env.observation_size());
+        assert_eq!(step_info.rewards.len(), 2);
+    }
+}
+
+#[test]
+fn test_shared_memory_matches_socket_transport() {
+    let program = Path::new(env!("CARGO_BIN_EXE_retro_worker"));
+    let mut socket_env = VecRetroEnv::with_transport(program, specs(), Transport::Socket).unwrap();
+    let mut shared_env = VecRetroEnv::with_transport(program, specs(), Transport::SharedMemory).unwrap();
+
+    socket_env.reset_with_seed(0).unwrap();
+    shared_env.reset_with_seed(0).unwrap();
+
+    for i in 0..50 {
+        let actions = [i % socket_env.num_actions(), 0];
+        let expected = socket_env.step(&actions).unwrap();
+        let batch = shared_env.step_in_place(&actions).unwrap();
+
+        assert_eq!(batch.slab.observations(), expected.observations.as_slice());
+        assert_eq!(batch.slab.rewards(), expected.rewards.as_slice());
+    }
+}
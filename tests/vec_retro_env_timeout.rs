@@ -0,0 +1,31 @@
+#![cfg(unix)]
+
+use std::env;
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::{Duration, Instant};
+use retro_rust::environments::image_retro_env::platform::Platform;
+use retro_rust::environments::env_builder::EnvBuilder;
+use retro_rust::environments::vec_retro_env::{Transport, VecEnvError, VecRetroEnv};
+use retro_rust::environments::vec_retro_env::worker::WORKER_SOCKET_ENV;
+
+#[test]
+fn test_stalled_worker_times_out() {
+    // The test binary is its own worker, it connects and then never answers
+    if let Ok(socket_path) = env::var(WORKER_SOCKET_ENV) {
+        let _stream = UnixStream::connect(socket_path).unwrap();
+        thread::sleep(Duration::from_secs(60));
+        return;
+    }
+
+    let specs = vec![EnvBuilder::new("Airstriker", Platform::Genesis, String::from("Level1.state"))];
+    let start = Instant::now();
+    let result = VecRetroEnv::with_reply_timeout(
+        &env::current_exe().unwrap(),
+        specs,
+        Transport::Socket,
+        Duration::from_millis(500)
+    );
+    assert!(matches!(result, Err(VecEnvError::WorkerTimeout { worker: 0 })));
+    assert!(start.elapsed() < Duration::from_secs(30));
+}